@@ -0,0 +1,29 @@
+#[macro_use]
+extern crate criterion;
+extern crate packt_core;
+extern crate rand;
+
+use criterion::Criterion;
+use packt_core::geometry::Rectangle;
+use packt_core::problem::Generator;
+
+fn split_into_10000(c: &mut Criterion) {
+    c.bench_function("split_into_10000", |b| {
+        let mut rng = rand::thread_rng();
+        let r = Rectangle::new(1000, 500);
+
+        b.iter(|| r.split_into(10_000, &mut rng))
+    });
+}
+
+fn generate_5000(c: &mut Criterion) {
+    c.bench_function("generate_5000", |b| {
+        let mut generator = Generator::new();
+        generator.rectangles(5000);
+
+        b.iter(|| generator.generate())
+    });
+}
+
+criterion_group!(benches, split_into_10000, generate_5000);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+extern crate failure;
+extern crate log;
+extern crate packt_core;
+#[macro_use]
+extern crate quicli;
+
+use packt_core::solution::Solution;
+use quicli::prelude::*;
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+/// Evaluates a solution read from stdin, i.e. the problem followed by the placements, as
+/// printed by a solver. Exits with a non-zero code if the solution is invalid
+#[derive(Debug, StructOpt)]
+struct Cli {
+    /// Accept solutions that leave rectangles unplaced, leaving them out of the evaluation
+    #[structopt(long = "partial")]
+    partial: bool,
+
+    #[structopt(flatten)]
+    verbosity: Verbosity,
+}
+
+main!(|args: Cli, log_level: verbosity| {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    let mut solution = Solution::extract_from(&input)?;
+    if args.partial {
+        solution.accept_partial();
+    }
+
+    // The solver was not run here, so there is no duration to report
+    let evaluation = solution.evaluate(Duration::from_secs(0))?;
+    println!("{}", evaluation);
+});
@@ -4,10 +4,16 @@ extern crate packt_core;
 #[macro_use]
 extern crate quicli;
 
-use packt_core::problem;
+use packt_core::{
+    geometry::Rectangle,
+    problem::{self, Generator, Problem},
+};
 use quicli::prelude::*;
 use std::{fs::OpenOptions, io, path::PathBuf};
 
+/// The average area of the rectangles cut from a container, as in the library generator.
+const AVG_RECTANGLE_AREA: u64 = 50;
+
 #[derive(Debug, StructOpt)]
 struct Cli {
     /// Amount of rectangles to generate
@@ -35,14 +41,96 @@ struct Cli {
 
 main!(|args: Cli, log_level: verbosity| {
     let n = args.count;
-    let variant = args.variant;
-    let rotation = args.rotation;
-    let problem = problem::generate(n, variant, rotation);
+    let mut generator = Generator::new();
+    if let Some(variant) = args.variant {
+        if let problem::Variant::Fixed(k) = variant {
+            // Cut a container of the requested height, instead of one of random height
+            generator.container(fixed_container(n, k)?);
+        }
+        generator.variant(variant);
+    }
+    if let Some(rotation) = args.rotation {
+        generator.allow_rotation(rotation);
+    }
+    generator.rectangles(n);
+    let problem = generator.generate();
 
     let mut dest: Box<dyn io::Write> = match args.output {
         Some(path) => Box::new(OpenOptions::new().write(true).create(true).open(path)?),
         None => Box::new(io::stdout()),
     };
 
-    dest.write_all(problem.to_string().as_bytes())?;
+    write_problem(&problem, &mut dest)?;
 });
+
+/// A container of height `k` with room for `n` rectangles of the usual average area.
+fn fixed_container(n: usize, k: u32) -> Result<Rectangle> {
+    let k = u64::from(k.max(1));
+    let width = (n as u64)
+        .checked_mul(AVG_RECTANGLE_AREA)
+        .map(|area| (area / k + (area % k).min(1)).max(1))
+        .filter(|&width| width <= u64::from(u32::max_value()))
+        .ok_or_else(|| {
+            failure::err_msg(format!(
+                "A container of height {} for {} rectangles is too wide",
+                k, n
+            ))
+        })?;
+
+    Ok(Rectangle::new(width as u32, k as u32))
+}
+
+/// Writes `problem`, preceded by comments with its source and rectangle count if it has a
+/// source.
+fn write_problem<W: io::Write>(problem: &Problem, dest: &mut W) -> io::Result<()> {
+    if let Some(source) = problem.source {
+        writeln!(dest, "# source: {}", source)?;
+        writeln!(dest, "# count: {}", problem.rectangle_count())?;
+    }
+
+    problem.to_writer(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_containers() {
+        let container = fixed_container(5000, 10).unwrap();
+        assert_eq!(container, Rectangle::new(25_000, 10));
+        assert_eq!(fixed_container(3, 7).unwrap(), Rectangle::new(22, 7));
+        assert_eq!(fixed_container(0, 0).unwrap(), Rectangle::new(1, 1));
+        assert!(fixed_container(usize::max_value(), 1).is_err());
+        assert!(fixed_container(1 << 40, 1).is_err());
+
+        let mut generator = Generator::new();
+        generator.container(container);
+        generator.rectangles(5000);
+        let problem = generator.generate();
+        assert!(problem.rectangles().iter().any(|r| r.area() > 1));
+    }
+
+    #[test]
+    fn source_header() {
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(20, 10));
+        generator.rectangles(15);
+        let problem = generator.generate();
+
+        let mut output = Vec::new();
+        write_problem(&problem, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let mut lines = output.lines();
+        let source: Rectangle = lines
+            .next()
+            .and_then(|line| line.trim_start_matches("# source: ").parse().ok())
+            .unwrap();
+        assert_eq!(source, Rectangle::new(20, 10));
+        assert_eq!(lines.next(), Some("# count: 15"));
+
+        let parsed: Problem = output.parse().unwrap();
+        assert_eq!(parsed.rectangles(), problem.rectangles());
+    }
+}
@@ -1,32 +1,63 @@
 extern crate failure;
+#[macro_use]
 extern crate log;
 extern crate packt_core;
 #[macro_use]
 extern crate quicli;
 extern crate csv;
+extern crate ctrlc;
+extern crate num_cpus;
+extern crate rand;
 extern crate serde;
+extern crate serde_json;
 extern crate tokio;
 extern crate tokio_core;
 extern crate tokio_io;
 extern crate tokio_process;
+extern crate toml;
 #[macro_use]
 extern crate serde_derive;
 
-use packt_core::{problem::Problem, runner, solution::Evaluation};
+use packt_core::{
+    problem::Problem,
+    runner,
+    solution::{secs, Evaluation, Phases, Solution},
+};
 use quicli::prelude::*;
+use rand::{Rng, SeedableRng, StdRng};
 use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fs::{self, OpenOptions},
     io,
-    path::PathBuf,
-    time::Duration,
+    path::{Path, PathBuf},
+    process, result,
+    str::FromStr,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 use tokio_core::reactor::Core;
 
+/// Runs a solver on every problem in a directory and reports the evaluations.
+///
+/// On Ctrl-C or SIGTERM the running solver is killed, the results of the runs that finished
+/// are written and flushed, and the program exits with code 130. A second signal exits
+/// right away.
 #[derive(Debug, StructOpt)]
 struct Cli {
-    /// Solver jar-file to solve with
-    #[structopt(parse(from_os_str))]
-    solver: PathBuf,
+    /// Solver jar-file or executable to solve with. Required unless the solvers are given by
+    /// `--config`
+    #[structopt(
+        long = "solver",
+        short = "s",
+        parse(from_os_str),
+        required_unless = "config",
+        conflicts_with = "config"
+    )]
+    solver: Option<PathBuf>,
 
     /// Location of the directory with the input files
     #[structopt(parse(from_os_str))]
@@ -36,50 +67,726 @@ struct Cli {
     #[structopt(parse(from_os_str))]
     output: Option<PathBuf>,
 
+    /// Write the results of every input to `<input name>.result.csv` (or `.json` with
+    /// `--pretty-json`) in this directory,
+    /// instead of to a single output. Cannot be combined with an output file, and ignores
+    /// `--sort`
+    #[structopt(long = "output-per-file", parse(from_os_str))]
+    output_per_file: Option<PathBuf>,
+
     /// Timeout to run the solver with, in seconds.
     /// Defaults to 300 seconds if not present
     #[structopt(long = "timeout", short = "t")]
     timeout: Option<u64>,
 
+    /// Stop launching the solver once this many seconds have passed since the start, for a
+    /// hard budget on the whole sweep. A run that is already going still gets its own timeout.
+    /// The remaining instances are written to the output as skipped, which does not count as
+    /// a failure
+    #[structopt(long = "total-timeout")]
+    total_timeout: Option<u64>,
+
+    /// TOML file listing named solvers to run on every instance, instead of a single solver.
+    /// Adds a `solver` column to the output. See `SolverConfig` for the format
+    #[structopt(long = "config", parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Pass an argument to the JVM of the solver, e.g. `--jvm-arg=-Xmx4g`. May be repeated.
+    /// Added after the JVM arguments from `--config`, and ignored for solvers that are not
+    /// jar-files
+    #[structopt(long = "jvm-arg", raw(number_of_values = "1"))]
+    jvm_args: Vec<String>,
+
+    /// Pass an argument to the solver itself, after the jar-file, e.g.
+    /// `--solver-arg=--algo=bl`. May be repeated. Added after the solver arguments from
+    /// `--config`
+    #[structopt(long = "solver-arg", raw(number_of_values = "1"))]
+    solver_args: Vec<String>,
+
+    /// Limit the memory of each solver process to this many MiB, so that a solver using too
+    /// much fails on that instance instead of taking down the machine. Only supported on
+    /// Unix, where it limits the address space; ignored elsewhere. Leave room above `-Xmx`
+    /// for the JVM
+    #[structopt(long = "max-memory")]
+    max_memory: Option<u64>,
+
+    /// Pin each solver process to a single CPU core, cycling through the available cores.
+    /// Only supported on Linux, where it requires `taskset`; ignored elsewhere, and with a
+    /// warning if `taskset` is missing
+    #[structopt(long = "pin-cores")]
+    pin_cores: bool,
+
+    /// Report the time spent parsing, solving and evaluating every instance on stderr
+    #[structopt(long = "profile")]
+    profile: bool,
+
+    /// Process the input files in a random order instead of directory order
+    #[structopt(long = "shuffle")]
+    shuffle: bool,
+
+    /// Seed for `--shuffle`, so the same order can be reproduced. Implies `--shuffle`
+    #[structopt(long = "seed")]
+    seed: Option<usize>,
+
+    /// CSV file with a header row followed by `filename,area` rows, giving the optimal
+    /// container area of known instances. Adds a `ratio` column for those instances
+    #[structopt(long = "optimal-csv", parse(from_os_str))]
+    optimal_csv: Option<PathBuf>,
+
+    /// Buffer all records and write them sorted by filename once every instance is solved,
+    /// so the output does not depend on the order the instances were run in.
+    /// Shorthand for `--sort filename`
+    #[structopt(long = "sort-output")]
+    sort_output: bool,
+
+    /// Sort the output by `filename`, `filling_rate` (best first) or `duration` (fastest
+    /// first). Failed instances come last. Records are buffered until every instance is
+    /// solved, so memory grows with the number of instances; without sorting they are
+    /// written as soon as they are available
+    #[structopt(long = "sort")]
+    sort: Option<SortField>,
+
+    /// Write the results as an indented JSON array instead of CSV, with the keys in the
+    /// order of the CSV columns. Like sorting, this buffers all records until every instance
+    /// is solved
+    #[structopt(long = "pretty-json")]
+    pretty_json: bool,
+
+    /// Before the measured sweep, run the solver once on the first instance and discard the
+    /// result. Every instance still starts a fresh solver process, so this warms up disk and
+    /// OS caches for the solver rather than the JIT of a shared JVM
+    #[structopt(long = "warmup")]
+    warmup: bool,
+
+    /// Exit successfully even if some instances failed or timed out.
+    /// By default the exit code is non-zero if any instance did not produce a valid solution
+    #[structopt(long = "allow-failures")]
+    allow_failures: bool,
+
+    /// Stop at the first instance that fails or does not produce a valid solution, printing
+    /// the file and the reason, and exit with a non-zero code. The results so far are still
+    /// written
+    #[structopt(long = "fail-fast")]
+    fail_fast: bool,
+
+    /// Print a breakdown of how every evaluation was computed to stderr, including the ratio
+    /// to the optimal area if it is given by `--optimal-csv`
+    #[structopt(long = "explain")]
+    explain: bool,
+
+    /// Fill the `fragmentation` column with the number of maximal empty rectangles in every
+    /// solution. Left empty by default, as it takes time and memory linear in the area of the
+    /// container
+    #[structopt(long = "fragmentation")]
+    fragmentation: bool,
+
+    /// Suppress all log output, the `[k/total] filename ... done` progress lines printed to
+    /// stderr after every instance, the timings of `--profile` and the summaries of failed
+    /// and skipped runs. The exit code still reflects failures
+    #[structopt(long = "quiet", short = "q")]
+    quiet: bool,
+
     #[structopt(flatten)]
     verbosity: Verbosity,
 }
 
-
 main!(|args: Cli, log_level: verbosity| {
+    let sweep_start = Instant::now();
+    if args.quiet {
+        log::set_max_level(log::LevelFilter::Off);
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = interrupted.clone();
+        ctrlc::set_handler(move || {
+            if interrupted.swap(true, atomic::Ordering::SeqCst) {
+                process::exit(130);
+            }
+
+            eprintln!("Interrupted, stopping the running solvers");
+            runner::kill_live_children();
+        })?;
+    }
+
+    let mut solvers = match args.config {
+        Some(ref config) => load_config(config)?,
+        None => {
+            let solver = args
+                .solver
+                .clone()
+                .ok_or_else(|| failure::err_msg("Give a solver with --solver or --config"))?;
+            vec![SolverConfig::new(solver)]
+        }
+    };
+    for solver in &mut solvers {
+        solver.jvm_args.extend(args.jvm_args.iter().cloned());
+        solver.solver_args.extend(args.solver_args.iter().cloned());
+    }
+
+    if args.output_per_file.is_some() && args.output.is_some() {
+        return Err(failure::err_msg(
+            "--output-per-file cannot be combined with an output file",
+        ));
+    }
+
     let output: Box<dyn io::Write> = match args.output {
-        Some(path) => Box::new(OpenOptions::new().append(true).create(true).open(path)?),
+        Some(ref path) => Box::new(OpenOptions::new().append(true).create(true).open(path)?),
         None => Box::new(io::stdout()),
     };
 
     let mut writer = csv::Writer::from_writer(output);
     let timeout = args.timeout.unwrap_or(300);
-    let deadline = Duration::from_secs(timeout);
     let mut core = Core::new().unwrap();
+    let max_memory = match args.max_memory {
+        Some(mib) => Some(
+            mib.checked_mul(1 << 20)
+                .ok_or_else(|| failure::err_msg("--max-memory is too large"))?,
+        ),
+        None => None,
+    };
+    let command = |solver: &SolverConfig, cpu: Option<usize>| {
+        let mut command =
+            runner::command_with_args(&solver.path, &solver.jvm_args, &solver.solver_args);
+        if let Some(bytes) = max_memory {
+            command = command.max_memory(bytes);
+        }
+
+        match cpu {
+            Some(cpu) => command.pin_to(cpu).build(),
+            None => command.build(),
+        }
+    };
+
+    let mut entries = args.input.read_dir()?.collect::<io::Result<Vec<_>>>()?;
+    if args.shuffle || args.seed.is_some() {
+        shuffle(&mut entries, args.seed);
+    }
+
+    if args.warmup {
+        // Inputs that cannot be read or parsed fail in the sweep, so warm up on one that can
+        let first = entries
+            .iter()
+            .filter_map(|entry| {
+                let input = fs::read_to_string(entry.path()).ok()?;
+                Some((entry, input.parse::<Problem>().ok()?))
+            })
+            .next();
+        if let Some((entry, problem)) = first {
+            info!("Warming up on {}", entry.file_name().to_string_lossy());
+            for solver in &solvers {
+                if interrupted.load(atomic::Ordering::SeqCst) {
+                    break;
+                }
+
+                let deadline = solver.deadline(timeout);
+                let warmup = runner::run_command_async(
+                    command(solver, None),
+                    problem.clone(),
+                    core.handle(),
+                    deadline,
+                );
+                if let Err(e) = core.run(warmup) {
+                    warn!("Warmup run of {} failed: {}", solver.name, e);
+                }
+            }
+        }
+    }
+
+    let total = entries.len() * solvers.len();
+    let summary = sweep(
+        &args,
+        sweep_start,
+        &solvers,
+        entries,
+        &interrupted,
+        &mut writer,
+        |solver, cpu, problem| {
+            let child = runner::run_command_async(
+                command(solver, cpu),
+                problem.clone(),
+                core.handle(),
+                solver.deadline(timeout),
+            );
+            core.run(child)
+        },
+    )?;
+
+    let mut records = summary.records;
+    if args.pretty_json {
+        if let Some(field) = args.sort_field() {
+            sort_records(&mut records, field);
+        }
+        write_pretty_json(writer.get_mut(), &records)?;
+    } else if let Some(field) = args.sort_field() {
+        write_sorted(&mut writer, records, field)?;
+    }
+
+    writer.flush()?;
+
+    if interrupted.load(atomic::Ordering::SeqCst) {
+        eprintln!("Interrupted, the results of the finished runs were written");
+        process::exit(130);
+    }
+
+    if let Some(message) = summary.halted {
+        eprintln!("Stopped at the first failure: {}", message);
+        process::exit(1);
+    }
+
+    if summary.skipped > 0 && !args.quiet {
+        eprintln!(
+            "{} of {} runs were skipped once the total timeout ran out",
+            summary.skipped, total
+        );
+    }
+
+    if !summary.failures.is_empty() {
+        if !args.quiet {
+            eprintln!("{} of {} instances failed:", summary.failures.len(), total);
+            for (filename, error) in &summary.failures {
+                eprintln!("  {}: {}", filename, error);
+            }
+        }
+
+        if !args.allow_failures {
+            process::exit(1);
+        }
+    }
+});
+
+impl Cli {
+    /// The field to sort the output by, if any.
+    fn sort_field(&self) -> Option<SortField> {
+        if self.sort_output {
+            Some(SortField::Filename)
+        } else {
+            self.sort
+        }
+    }
+}
+
+/// How a sweep went, see `sweep`.
+#[derive(Debug, Default)]
+struct Summary {
+    /// The records to write once the sweep is done, if the output is sorted or JSON.
+    records: Vec<Record>,
+    /// The label and error of every run that failed.
+    failures: Vec<(String, String)>,
+    /// The number of runs skipped once the total timeout ran out.
+    skipped: usize,
+    /// Why the sweep stopped early under `--fail-fast`.
+    halted: Option<String>,
+}
+
+/// Runs every solver on every problem in `entries` with `run`, which solves a problem with a
+/// solver, pinned to a core if one is given. The total timeout counts from `started`.
+///
+/// Records are written to `writer` as soon as they are available, unless they go to a file
+/// per input or are returned to be written sorted or as JSON. Stops early once `interrupted`
+/// is set, or at the first failure under `--fail-fast`.
+fn sweep<W, F>(
+    args: &Cli,
+    started: Instant,
+    solvers: &[SolverConfig],
+    entries: Vec<fs::DirEntry>,
+    interrupted: &AtomicBool,
+    writer: &mut csv::Writer<W>,
+    mut run: F,
+) -> Result<Summary>
+where
+    W: io::Write,
+    F: FnMut(&SolverConfig, Option<usize>, &Problem) -> Result<(Solution, Duration, Phases)>,
+{
+    let optimal = match args.optimal_csv {
+        Some(ref path) => read_optimal(path)?,
+        None => HashMap::new(),
+    };
 
-    for entry in args.input.read_dir()? {
-        let entry = entry?;
+    let total_timeout = args.total_timeout.map(Duration::from_secs);
+    let cores = num_cpus::get();
+    let total = entries.len() * solvers.len();
+    let sort = args.sort_field();
+    let mut summary = Summary::default();
+    for (i, entry) in entries.into_iter().enumerate() {
         let filename = entry.file_name();
         let filestr = filename.to_string_lossy().to_owned();
-        eprintln!("\nRunning {}", filestr);
+        info!("Running {}", filestr);
+
+        let parse_start = Instant::now();
+        let problem = fs::read_to_string(entry.path())
+            .map_err(failure::Error::from)
+            .and_then(|input| input.parse::<Problem>());
+        let parse_time = parse_start.elapsed();
+
+        let problem = match problem {
+            Ok(problem) => problem,
+            Err(e) => {
+                // No solver can run on this input, so it fails once instead of once per solver
+                warn!("Skipping {}: {}", filestr, e);
+                let record = Record::unreadable(&filestr, &e);
+                if args.fail_fast {
+                    summary.halted = fail_fast(&filestr, &record);
+                }
+                summary.failures.push((filestr.to_string(), e.to_string()));
+
+                match args.output_per_file {
+                    Some(ref dir) => {
+                        write_result_file(dir, &filestr, vec![record], args.pretty_json)?;
+                    }
+                    None if sort.is_some() || args.pretty_json => summary.records.push(record),
+                    None => writer.serialize(record)?,
+                }
+                if summary.halted.is_some() {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        let mut instance_records = Vec::new();
+        for (j, solver) in solvers.iter().enumerate() {
+            // Interrupted while warming up, reading the input or between two runs, when no
+            // solver was running to be killed
+            if interrupted.load(atomic::Ordering::SeqCst) {
+                break;
+            }
+
+            let k = i * solvers.len() + j;
+            let label = match args.config {
+                Some(_) => format!("{} [{}]", filestr, solver.name),
+                None => filestr.to_string(),
+            };
+
+            let optimal_area = optimal.get(&*filestr).cloned();
+            let out_of_time = total_timeout.map_or(false, |t| started.elapsed() >= t);
+            let evaluation = if out_of_time {
+                summary.skipped += 1;
+                Err(failure::err_msg("skipped, the total timeout ran out"))
+            } else {
+                let cpu = if args.pin_cores {
+                    Some(k % cores)
+                } else {
+                    None
+                };
+                let solve_start = Instant::now();
+                let result = run(solver, cpu, &problem);
+                let solve_time = solve_start.elapsed();
+
+                let eval_start = Instant::now();
+                let evaluation = result.and_then(|(mut solution, duration, phases)| {
+                    if args.fragmentation {
+                        solution.measure_fragmentation();
+                    }
+                    runner::evaluate((solution, duration, phases))
+                });
+                let eval_time = eval_start.elapsed();
+
+                if args.profile && !args.quiet {
+                    eprintln!(
+                        "{}: parse {:.3}s, solve {:.3}s, evaluate {:.3}s",
+                        label,
+                        secs(parse_time),
+                        secs(solve_time),
+                        secs(eval_time),
+                    );
+                }
+                if !args.quiet {
+                    eprintln!(
+                        "{}",
+                        progress_line(k + 1, total, &label, &evaluation, solve_time)
+                    );
+                }
+
+                if args.explain {
+                    if let Ok(ref evaluation) = evaluation {
+                        eprintln!("{}:\n{}", label, evaluation.explain());
+                        if let Some(optimal_area) = optimal_area {
+                            eprintln!(
+                                "optimality ratio: {} / {} = {:.3}",
+                                evaluation.container.area(),
+                                optimal_area,
+                                evaluation.ratio_to(optimal_area)
+                            );
+                        }
+                    }
+                }
+
+                evaluation
+            };
+
+            // The run that was going on when interrupted was killed, so it has no result
+            if interrupted.load(atomic::Ordering::SeqCst) {
+                break;
+            }
+
+            let mut record = Record::new(&problem, evaluation, optimal_area, &filestr);
+            if args.config.is_some() {
+                record.solver = Some(solver.name.clone());
+            }
+            if args.fail_fast && !out_of_time {
+                summary.halted = fail_fast(&label, &record);
+            }
+            match record.error {
+                Some(ref error) if !out_of_time => summary.failures.push((label, error.clone())),
+                _ => (),
+            }
+
+            if args.output_per_file.is_some() {
+                instance_records.push(record);
+            } else if sort.is_some() || args.pretty_json {
+                summary.records.push(record);
+            } else {
+                writer.serialize(record)?;
+            }
+
+            if summary.halted.is_some() {
+                break;
+            }
+        }
+
+        // Also when interrupted, so the runs that finished on this input are not lost
+        let stopped = interrupted.load(atomic::Ordering::SeqCst);
+        if let Some(ref dir) = args.output_per_file {
+            if !(stopped && instance_records.is_empty()) {
+                write_result_file(dir, &filestr, instance_records, args.pretty_json)?;
+            }
+        }
+        if stopped || summary.halted.is_some() {
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// The message to stop the sweep with under `--fail-fast`, if the run of `label` failed.
+fn fail_fast(label: &str, record: &Record) -> Option<String> {
+    record
+        .error
+        .as_ref()
+        .map(|error| format!("{}: {}", label, error))
+}
+
+/// A solver matrix, read from the file given by `--config`.
+///
+/// ```toml
+/// [[solver]]
+/// name = "bottom-left"         # shown in the `solver` column, must be unique
+/// path = "solvers/bl.jar"      # relative to the config file
+/// jvm_args = ["-Xmx4g"]        # optional, only used for jar-files
+/// solver_args = ["--algo=bl"]  # optional
+/// timeout = 60                 # optional, in seconds, defaults to `--timeout`
+/// ```
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(rename = "solver", default)]
+    solvers: Vec<SolverConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SolverConfig {
+    name: String,
+    path: PathBuf,
+    #[serde(default)]
+    jvm_args: Vec<String>,
+    #[serde(default)]
+    solver_args: Vec<String>,
+    timeout: Option<u64>,
+}
+
+impl SolverConfig {
+    fn new(path: PathBuf) -> Self {
+        SolverConfig {
+            name: path.display().to_string(),
+            path,
+            jvm_args: Vec::new(),
+            solver_args: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    fn deadline(&self, default_timeout: u64) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(default_timeout))
+    }
+}
+
+/// Reads the solvers from the config file at `path`, failing if any of them does not exist.
+fn load_config(path: &Path) -> Result<Vec<SolverConfig>> {
+    let config: Config = toml::from_str(&fs::read_to_string(path)?)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    if config.solvers.is_empty() {
+        return Err(failure::err_msg(format!(
+            "No solvers listed in {}",
+            path.display()
+        )));
+    }
+
+    let mut names = HashSet::new();
+    let mut solvers = config.solvers;
+    for solver in &mut solvers {
+        solver.path = dir.join(&solver.path);
+        if !solver.path.is_file() {
+            return Err(failure::err_msg(format!(
+                "Solver {} not found at {}",
+                solver.name,
+                solver.path.display()
+            )));
+        }
+        if !names.insert(solver.name.clone()) {
+            return Err(failure::err_msg(format!(
+                "Solver name {} is used more than once",
+                solver.name
+            )));
+        }
+    }
+
+    Ok(solvers)
+}
+
+fn shuffle<T>(items: &mut [T], seed: Option<usize>) {
+    match seed {
+        Some(seed) => StdRng::from_seed(&[seed][..]).shuffle(items),
+        None => rand::thread_rng().shuffle(items),
+    }
+}
+
+fn progress_line(
+    k: usize,
+    total: usize,
+    filename: &str,
+    evaluation: &Result<Evaluation>,
+    elapsed: Duration,
+) -> String {
+    let status = match evaluation {
+        Ok(evaluation) => format!("done (rate={:.2})", evaluation.filling_rate),
+        Err(e) => format!("failed ({})", e),
+    };
+
+    format!(
+        "[{}/{}] {} ... {} in {:.1}s",
+        k,
+        total,
+        filename,
+        status,
+        secs(elapsed)
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SortField {
+    Filename,
+    FillingRate,
+    Duration,
+}
+
+impl FromStr for SortField {
+    type Err = failure::Error;
 
-        let mut input = fs::read_to_string(entry.path())?;
-        let problem = input.parse::<Problem>()?;
+    fn from_str(s: &str) -> Result<Self> {
+        let field = match s {
+            "filename" => SortField::Filename,
+            "filling_rate" => SortField::FillingRate,
+            "duration" => SortField::Duration,
+            _ => return Err(failure::err_msg(format!("Unknown sort field: {}", s))),
+        };
+
+        Ok(field)
+    }
+}
 
-        let handle = core.handle();
-        let child = runner::solve_async(&args.solver, problem.clone(), handle, deadline);
-        let evaluation = core.run(child);
-        let record = Record::new(&problem, evaluation, &filestr);
+/// Orders present values before missing ones.
+fn cmp_present<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn sort_records(records: &mut [Record], field: SortField) {
+    records.sort_by(|a, b| {
+        let by_field = match field {
+            SortField::Filename => Ordering::Equal,
+            SortField::FillingRate => {
+                cmp_present(a.filling_rate.map(|r| -r), b.filling_rate.map(|r| -r))
+            }
+            SortField::Duration => cmp_present(a.elapsed, b.elapsed),
+        };
+
+        by_field
+            .then_with(|| a.filename.cmp(&b.filename))
+            .then_with(|| a.solver.cmp(&b.solver))
+    });
+}
 
+fn write_sorted<W: io::Write>(
+    writer: &mut csv::Writer<W>,
+    mut records: Vec<Record>,
+    field: SortField,
+) -> Result<()> {
+    sort_records(&mut records, field);
+    for record in records {
         writer.serialize(record)?;
     }
 
+    Ok(())
+}
+
+fn write_pretty_json<W: io::Write>(mut writer: W, records: &[Record]) -> Result<()> {
+    serde_json::to_writer_pretty(&mut writer, records)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Writes `records` to `<filename without extension>.result.csv` in `dir`, or to
+/// `.result.json` if `json` is set, creating `dir` if needed. Returns the path written to.
+fn write_result_file(
+    dir: &Path,
+    filename: &str,
+    records: Vec<Record>,
+    json: bool,
+) -> Result<PathBuf> {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map_or_else(|| filename.into(), |stem| stem.to_string_lossy());
+    let extension = if json { "json" } else { "csv" };
+    let path = dir.join(format!("{}.result.{}", stem, extension));
+
+    fs::create_dir_all(dir)?;
+    if json {
+        write_pretty_json(fs::File::create(&path)?, &records)?;
+        return Ok(path);
+    }
+
+    let mut writer = csv::Writer::from_path(&path)?;
+    for record in records {
+        writer.serialize(record)?;
+    }
     writer.flush()?;
-});
 
-#[derive(Debug, Serialize)]
-struct Record<'a> {
-    filename: &'a str,
+    Ok(path)
+}
+
+fn read_optimal(path: &PathBuf) -> Result<HashMap<String, u64>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let optimal = reader
+        .deserialize::<(String, u64)>()
+        .collect::<result::Result<_, _>>()?;
+    Ok(optimal)
+}
+
+/// The results of solving a single instance.
+///
+/// Fields are written in the order they are declared here, which is both the column order of
+/// the CSV output and the key order of the JSON output. Keep that order stable, and add new
+/// fields at the end.
+#[derive(Debug, Default, Serialize)]
+struct Record {
+    filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solver: Option<String>,
     n: usize,
     variant: String,
     rotation_allowed: bool,
@@ -90,18 +797,39 @@ struct Record<'a> {
     empty_area: Option<i64>,
     filling_rate: Option<f32>,
     duration: Option<String>,
+    #[serde(skip_serializing)]
+    elapsed: Option<Duration>,
+    rotations_used: Option<usize>,
+    fragmentation: Option<usize>,
+    efficiency: Option<f32>,
+    ratio: Option<f64>,
+    height_utilization: Option<f32>,
 }
 
-impl<'a> Record<'a> {
-    fn new<'b>(problem: &'b Problem, evaluation: Result<Evaluation>, filename: &'a str) -> Self {
+impl Record {
+    fn new(
+        problem: &Problem,
+        evaluation: Result<Evaluation>,
+        optimal_area: Option<u64>,
+        filename: &str,
+    ) -> Self {
         let &Problem {
             variant,
             allow_rotation,
-            ref rectangles,
             ..
         } = problem;
-        let n = rectangles.len();
+        let n = problem.rectangle_count();
 
+        let ratio = match evaluation {
+            Ok(ref eval) => optimal_area.map(|area| eval.ratio_to(area)),
+            Err(_) => None,
+        };
+
+        let elapsed = evaluation.as_ref().ok().map(|eval| eval.duration);
+        let rotations_used = evaluation.as_ref().ok().map(|eval| eval.rotations_used);
+        let fragmentation = evaluation.as_ref().ok().and_then(|eval| eval.fragmentation);
+        let efficiency = evaluation.as_ref().ok().and_then(|eval| eval.efficiency);
+        let height_utilization = evaluation.as_ref().ok().map(|eval| eval.height_utilization);
         let (container, min_area, empty_area, filling_rate, duration, error) = match evaluation {
             Ok(eval) => {
                 let Evaluation {
@@ -129,7 +857,8 @@ impl<'a> Record<'a> {
         };
 
         Record {
-            filename,
+            filename: filename.to_string(),
+            solver: None,
             n,
             variant: variant.to_string(),
             rotation_allowed: allow_rotation,
@@ -139,7 +868,417 @@ impl<'a> Record<'a> {
             empty_area,
             filling_rate,
             duration,
+            elapsed,
+            rotations_used,
+            fragmentation,
+            efficiency,
+            ratio,
+            height_utilization,
             error,
         }
     }
+
+    /// The record of an input that could not be read or parsed, so no solver ran on it.
+    fn unreadable(filename: &str, error: &failure::Error) -> Self {
+        Record {
+            filename: filename.to_string(),
+            perfect_packing: filename.contains("packt"),
+            error: Some(error.to_string()),
+            ..Record::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn seeded_shuffle() {
+        let files: Vec<String> = (0..20).map(|i| format!("{}.txt", i)).collect();
+
+        let mut first = files.clone();
+        shuffle(&mut first, Some(42));
+        let mut second = files.clone();
+        shuffle(&mut second, Some(42));
+
+        assert_eq!(first, second);
+        assert_ne!(first, files);
+    }
+
+    #[test]
+    fn progress() {
+        let failed = Err(failure::err_msg("timed out"));
+        assert_eq!(
+            progress_line(3, 10, "a.txt", &failed, Duration::from_millis(1500)),
+            "[3/10] a.txt ... failed (timed out) in 1.5s"
+        );
+    }
+
+    #[test]
+    fn sorted_output() {
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+        let files: Vec<String> = (0..20).map(|i| format!("{}.txt", i)).collect();
+
+        let run = |seed| {
+            let mut order = files.clone();
+            shuffle(&mut order, Some(seed));
+            let records = order
+                .iter()
+                .map(|f| Record::new(&problem, Err(failure::err_msg("timed out")), None, f))
+                .collect();
+
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            write_sorted(&mut writer, records, SortField::Filename).unwrap();
+            writer.into_inner().unwrap()
+        };
+
+        assert_eq!(run(1), run(2));
+    }
+
+    #[test]
+    fn sort_by_field() {
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+        let record = |filename, rate, secs: Option<u64>| {
+            let mut record = Record::new(&problem, Err(failure::err_msg("-")), None, filename);
+            record.filling_rate = rate;
+            record.elapsed = secs.map(Duration::from_secs);
+            record
+        };
+
+        let sorted = |field| {
+            let records = vec![
+                record("a.txt", Some(0.5), Some(3)),
+                record("b.txt", None, None),
+                record("c.txt", Some(0.9), Some(5)),
+                record("d.txt", Some(0.7), Some(1)),
+            ];
+
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            write_sorted(&mut writer, records, field).unwrap();
+            let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+            output
+                .lines()
+                .skip(1)
+                .map(|l| l[..1].to_string())
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            "filling_rate".parse::<SortField>().unwrap(),
+            SortField::FillingRate
+        );
+        assert_eq!(sorted(SortField::Filename), "abcd");
+        assert_eq!(sorted(SortField::FillingRate), "cdab");
+        assert_eq!(sorted(SortField::Duration), "dacb");
+    }
+
+    #[test]
+    fn fail_fast_sweep() {
+        let dir = env::temp_dir().join("packt_fail_fast");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                       2\n2 2\n2 2";
+        for file in &["a.txt", "b.txt", "c.txt"] {
+            fs::write(dir.join(file), problem).unwrap();
+        }
+
+        let mut entries = dir
+            .read_dir()
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.file_name());
+
+        let input = dir.to_string_lossy().into_owned();
+        let args = Cli::from_iter(&[
+            "solver",
+            "--solver",
+            "solver.jar",
+            "--fail-fast",
+            "--quiet",
+            &input,
+        ]);
+        let solvers = [SolverConfig::new("solver.jar".into())];
+        let interrupted = AtomicBool::new(false);
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        // The second solution places its rectangles on top of each other
+        let mut runs = 0;
+        let summary = sweep(
+            &args,
+            Instant::now(),
+            &solvers,
+            entries,
+            &interrupted,
+            &mut writer,
+            |_, _, _| {
+                runs += 1;
+                let second = if runs == 2 { "1 1" } else { "2 0" };
+                let solution = format!("{}\nplacement of rectangles\n0 0\n{}", problem, second)
+                    .parse::<Solution>()?;
+                let zero = Duration::from_secs(0);
+                let phases = Phases {
+                    spawn: zero,
+                    write: zero,
+                    wait: zero,
+                    parse: zero,
+                };
+                Ok((solution, zero, phases))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(runs, 2);
+        assert_eq!(
+            summary.halted,
+            Some("b.txt: Overlap in solution".to_string())
+        );
+        assert_eq!(summary.failures.len(), 1);
+
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        let filenames = output
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(filenames, ["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn unreadable_input() {
+        let dir = env::temp_dir().join("packt_unreadable_input");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 2";
+        fs::write(dir.join("a.txt"), "not a problem").unwrap();
+        fs::write(dir.join("b.txt"), problem).unwrap();
+
+        let input = dir.to_string_lossy().into_owned();
+        let solvers = [SolverConfig::new("solver.jar".into())];
+        let interrupted = AtomicBool::new(false);
+        for &fail_fast in &[false, true] {
+            let mut entries = dir
+                .read_dir()
+                .unwrap()
+                .map(|e| e.unwrap())
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|e| e.file_name());
+
+            let mut argv = vec!["solver", "--solver", "solver.jar", "--quiet", &input];
+            if fail_fast {
+                argv.push("--fail-fast");
+            }
+            let args = Cli::from_iter(&argv);
+            let mut writer = csv::Writer::from_writer(Vec::new());
+
+            let mut runs = 0;
+            let summary = sweep(
+                &args,
+                Instant::now(),
+                &solvers,
+                entries,
+                &interrupted,
+                &mut writer,
+                |_, _, _| {
+                    runs += 1;
+                    let solution =
+                        format!("{}\nplacement of rectangles\n0 0", problem).parse::<Solution>()?;
+                    let zero = Duration::from_secs(0);
+                    let phases = Phases {
+                        spawn: zero,
+                        write: zero,
+                        wait: zero,
+                        parse: zero,
+                    };
+                    Ok((solution, zero, phases))
+                },
+            )
+            .unwrap();
+
+            assert_eq!(summary.failures.len(), 1);
+            assert_eq!(summary.failures[0].0, "a.txt");
+            assert_eq!(summary.halted.is_some(), fail_fast);
+
+            let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+            let records = output.lines().skip(1).collect::<Vec<_>>();
+            if fail_fast {
+                assert_eq!(runs, 0);
+                assert_eq!(records.len(), 1);
+            } else {
+                assert_eq!(runs, 1);
+                assert_eq!(records.len(), 2);
+                assert!(records[1].starts_with("b.txt,"));
+            }
+            assert!(records[0].starts_with("a.txt,"));
+        }
+    }
+
+    #[test]
+    fn output_per_file() {
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+        let dir = env::temp_dir().join("packt_output_per_file");
+        let _ = fs::remove_dir_all(&dir);
+
+        for filename in &["a.txt", "b.txt"] {
+            let record = Record::new(&problem, Err(failure::err_msg("-")), None, filename);
+            write_result_file(&dir, filename, vec![record], false).unwrap();
+        }
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["a.result.csv", "b.result.csv"]);
+
+        let contents = fs::read_to_string(dir.join("b.result.csv")).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.lines().nth(1).unwrap().starts_with("b.txt,"));
+
+        let record = Record::new(&problem, Err(failure::err_msg("-")), None, "c.txt");
+        let path = write_result_file(&dir, "c.txt", vec![record], true).unwrap();
+        assert_eq!(path, dir.join("c.result.json"));
+    }
+
+    #[test]
+    fn interrupted_output_per_file() {
+        let dir = env::temp_dir().join("packt_interrupted");
+        let output = dir.join("results");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3";
+        for file in &["a.txt", "b.txt"] {
+            fs::write(dir.join(file), problem).unwrap();
+        }
+        let mut entries = dir
+            .read_dir()
+            .unwrap()
+            .map(|e| e.unwrap())
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.file_name());
+
+        let input = dir.to_string_lossy().into_owned();
+        let output_arg = output.to_string_lossy().into_owned();
+        let args = Cli::from_iter(&[
+            "solver",
+            "--solver",
+            "solver.jar",
+            "--quiet",
+            "--output-per-file",
+            &output_arg,
+            &input,
+        ]);
+        let solvers = [
+            SolverConfig::new("first.jar".into()),
+            SolverConfig::new("second.jar".into()),
+        ];
+        let interrupted = AtomicBool::new(false);
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        // Interrupted while the second solver runs on the first input
+        let mut runs = 0;
+        let summary = sweep(
+            &args,
+            Instant::now(),
+            &solvers,
+            entries,
+            &interrupted,
+            &mut writer,
+            |_, _, _| {
+                runs += 1;
+                if runs == 2 {
+                    interrupted.store(true, atomic::Ordering::SeqCst);
+                }
+                let solution =
+                    format!("{}\nplacement of rectangles\n0 0", problem).parse::<Solution>()?;
+                let zero = Duration::from_secs(0);
+                let phases = Phases {
+                    spawn: zero,
+                    write: zero,
+                    wait: zero,
+                    parse: zero,
+                };
+                Ok((solution, zero, phases))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(runs, 2);
+        assert!(summary.failures.is_empty());
+
+        let files = fs::read_dir(&output).unwrap().count();
+        assert_eq!(files, 1);
+        let contents = fs::read_to_string(output.join("a.result.csv")).unwrap();
+        assert_eq!(contents.lines().count(), 2);
+    }
+
+    #[test]
+    fn pretty_json() {
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+        let record = Record::new(&problem, Err(failure::err_msg("timed out")), None, "a.txt");
+
+        let mut buffer = Vec::new();
+        write_pretty_json(&mut buffer, &[record]).unwrap();
+        let json = String::from_utf8(buffer).unwrap();
+        assert!(json.contains("\n    \"filename\": \"a.txt\",\n"));
+
+        let keys = [
+            "\"filename\"",
+            "\"n\"",
+            "\"error\"",
+            "\"filling_rate\"",
+            "\"ratio\"",
+            "\"height_utilization\"",
+        ];
+        let positions: Vec<usize> = keys.iter().map(|key| json.find(key).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(!json.contains("\"elapsed\""));
+    }
+
+    #[test]
+    fn config() {
+        let dir = env::temp_dir().join(format!("packt_solver_config_{}", process::id()));
+        fs::create_dir_all(dir.join("solvers")).unwrap();
+        fs::write(dir.join("solvers/bl.jar"), "").unwrap();
+
+        let path = dir.join("matrix.toml");
+        let bl = "[[solver]]\nname = \"bl\"\npath = \"solvers/bl.jar\"\n\
+                  jvm_args = [\"-Xmx4g\"]\ntimeout = 60\n";
+        fs::write(&path, bl).unwrap();
+
+        let solvers = load_config(&path).unwrap();
+        assert_eq!(solvers.len(), 1);
+        assert_eq!(solvers[0].path, dir.join("solvers/bl.jar"));
+        assert_eq!(solvers[0].jvm_args, vec!["-Xmx4g"]);
+        assert!(solvers[0].solver_args.is_empty());
+        assert_eq!(solvers[0].deadline(300), Duration::from_secs(60));
+
+        let missing = "[[solver]]\nname = \"gone\"\npath = \"solvers/gone.jar\"\n";
+        fs::write(&path, format!("{}{}", bl, missing)).unwrap();
+        let error = load_config(&path).unwrap_err().to_string();
+        assert!(error.contains("Solver gone not found"));
+
+        fs::write(&path, format!("{}{}", bl, bl)).unwrap();
+        assert!(load_config(&path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -2,6 +2,7 @@ use self::Rotation::*;
 use failure::Error;
 use rand::distributions::{IndependentSample, Normal};
 use rand::{self, Rng};
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Formatter;
 use std::str::FromStr;
@@ -13,7 +14,7 @@ pub struct Point {
 }
 
 impl Point {
-    pub fn new(x: u32, y: u32) -> Point {
+    pub const fn new(x: u32, y: u32) -> Point {
         Point { x, y }
     }
 }
@@ -62,9 +63,185 @@ impl Rectangle {
         Rectangle { width, height }
     }
 
+    /// Generates a rectangle with exactly the given `area`, picking the pair of divisors whose
+    /// aspect ratio (width over height) is closest to `target`.
+    pub fn gen_with_aspect(area: u64, target: f32) -> Rectangle {
+        let target = f64::from(target);
+        let distance = |w: u64, h: u64| (w as f64 / h as f64 / target).ln().abs();
+
+        let (width, height) = (1..=(area as f64).sqrt() as u64)
+            .filter(|i| area % i == 0)
+            .flat_map(|d| vec![(d, area / d), (area / d, d)])
+            .min_by(|&(w1, h1), &(w2, h2)| {
+                distance(w1, h1)
+                    .partial_cmp(&distance(w2, h2))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap_or((1, area));
+
+        Rectangle::new(width as u32, height as u32)
+    }
+
+    /// Generates a rectangle with roughly the given `area`, whose sides approximate the ratio
+    /// `w_ratio:h_ratio` as closely as whole numbers allow.
+    pub fn gen_with_ratio(area: u64, w_ratio: u32, h_ratio: u32) -> Rectangle {
+        let ratio = f64::from(w_ratio.max(1)) / f64::from(h_ratio.max(1));
+        let width = ((area as f64 * ratio).sqrt().round() as u32).max(1);
+        let height = ((f64::from(width) / ratio).round() as u32).max(1);
+
+        Rectangle { width, height }
+    }
+
     pub fn simple_rsplit(self) -> (Rectangle, Rectangle) {
-        let mut rng = rand::thread_rng();
+        self.rsplit(&mut rand::thread_rng())
+    }
+
+    /// Splits this rectangle into `k` pieces with the same total area, by repeatedly cutting
+    /// a randomly chosen piece in two.
+    ///
+    /// `k` is clamped to the range `1..=self.area()`.
+    pub fn split_into<R: Rng>(self, k: usize, rng: &mut R) -> Vec<Rectangle> {
+        let k = k.max(1).min(self.area() as usize);
+        if k == self.area() as usize {
+            return vec![Rectangle::new(1, 1); k];
+        }
+
+        let mut rectangles = Vec::with_capacity(k);
+        rectangles.push(self);
+
+        while rectangles.len() < k {
+            let i = rng.gen_range(0, rectangles.len());
+            let r = rectangles[i];
+
+            if r.width > 1 || r.height > 1 {
+                let (r1, r2) = r.rsplit(rng);
+                rectangles[i] = r1;
+                rectangles.push(r2);
+            }
+        }
+
+        rectangles
+    }
+
+    /// Like `split_into`, but only makes cuts after which both pieces have an aspect ratio of
+    /// at most `max_aspect`, or at most that of the piece being cut if it is already more
+    /// elongated.
+    ///
+    /// Stops early, with fewer than `k` pieces, once no piece can be cut that way.
+    pub fn split_into_bounded<R: Rng>(
+        self,
+        k: usize,
+        max_aspect: f32,
+        rng: &mut R,
+    ) -> Vec<Rectangle> {
+        self.split_into_limited(k, Some(max_aspect), 1, rng)
+    }
+
+    /// Like `split_into`, but never cuts off a piece with an area below `min_area`, and only
+    /// makes cuts allowed by `split_into_bounded` if `max_aspect` is given.
+    ///
+    /// Stops early, with fewer than `k` pieces, once no piece can be cut that way.
+    pub fn split_into_limited<R: Rng>(
+        self,
+        k: usize,
+        max_aspect: Option<f32>,
+        min_area: u64,
+        rng: &mut R,
+    ) -> Vec<Rectangle> {
+        let k = k.max(1).min(self.area() as usize);
+        let mut rectangles = Vec::with_capacity(k);
+        let mut done = Vec::new();
+        rectangles.push(self);
+
+        while !rectangles.is_empty() && rectangles.len() + done.len() < k {
+            let i = rng.gen_range(0, rectangles.len());
+            let r = rectangles[i];
+            let pieces = match max_aspect {
+                Some(max_aspect) => r.bounded_rsplit(max_aspect, min_area, rng),
+                None => r.min_area_rsplit(min_area, rng),
+            };
+
+            match pieces {
+                Some((r1, r2)) => {
+                    rectangles[i] = r1;
+                    rectangles.push(r2);
+                }
+                None => done.push(rectangles.swap_remove(i)),
+            }
+        }
+
+        rectangles.extend(done);
+        rectangles
+    }
+
+    /// The ratio between the longest and the shortest side of this rectangle.
+    pub fn aspect_ratio(&self) -> f32 {
+        use std::cmp::{max, min};
+
+        max(self.width, self.height) as f32 / min(self.width, self.height) as f32
+    }
+
+    fn bounded_rsplit<R: Rng>(
+        self,
+        max_aspect: f32,
+        min_area: u64,
+        rng: &mut R,
+    ) -> Option<(Rectangle, Rectangle)> {
+        use std::cmp::{max, min};
+
+        let bound = self.aspect_ratio().max(max_aspect);
+        let (long, short) = (max(self.width, self.height), min(self.width, self.height));
+        let shortest = (short as f32 / bound).ceil() as u32;
+        let longest = (short as f32 * bound).floor() as u32;
+
+        let least = min_side(min_area, short);
+
+        let lo = max(least, max(shortest, long.saturating_sub(longest)));
+        let hi = min(
+            long.saturating_sub(least),
+            min(longest, long.saturating_sub(shortest)),
+        );
+        if long < 2 || lo > hi {
+            return None;
+        }
 
+        let at = rng.gen_range(lo, hi + 1);
+        let cut = if self.width >= self.height {
+            Cut::Vertical(at)
+        } else {
+            Cut::Horizontal(at)
+        };
+
+        Some(self.split(cut))
+    }
+
+    fn min_area_rsplit<R: Rng>(self, min_area: u64, rng: &mut R) -> Option<(Rectangle, Rectangle)> {
+        let range = |side: u32, across: u32| {
+            let least = min_side(min_area, across);
+            if side >= 2 * least {
+                Some((least, side - least))
+            } else {
+                None
+            }
+        };
+
+        let vertical = range(self.width, self.height);
+        let horizontal = range(self.height, self.width);
+        let cut = match (vertical, horizontal) {
+            (Some((lo, hi)), Some(_))
+                if rng.gen_range(0, self.width + self.height) < self.width =>
+            {
+                Cut::Vertical(rng.gen_range(lo, hi + 1))
+            }
+            (Some((lo, hi)), None) => Cut::Vertical(rng.gen_range(lo, hi + 1)),
+            (_, Some((lo, hi))) => Cut::Horizontal(rng.gen_range(lo, hi + 1)),
+            (None, None) => return None,
+        };
+
+        Some(self.split(cut))
+    }
+
+    fn rsplit<R: Rng>(self, rng: &mut R) -> (Rectangle, Rectangle) {
         let cut = match (self.width, self.height) {
             (1, 1) => panic!("{:?} cannot be split", self),
             (1, h) if h > 1 => {
@@ -90,13 +267,58 @@ impl Rectangle {
         self.split(cut)
     }
 
-    pub fn area(&self) -> u64 {
+    pub const fn area(&self) -> u64 {
         self.width as u64 * self.height as u64
     }
 
-    pub fn new(width: u32, height: u32) -> Rectangle {
+    /// The smallest rectangle covering the unit cells at `points`, i.e. spanning from the
+    /// lowest up to and including the highest coordinate in both directions. Empty if there
+    /// are no points.
+    pub fn bounding_of<I: IntoIterator<Item = Point>>(points: I) -> Rectangle {
+        use std::cmp::{max, min};
+
+        let mut points = points.into_iter();
+        let first = match points.next() {
+            Some(p) => p,
+            None => return Rectangle::new(0, 0),
+        };
+
+        let (bl, tr) = points.fold((first, first), |(bl, tr), p| {
+            let bl = Point::new(min(bl.x, p.x), min(bl.y, p.y));
+            let tr = Point::new(max(tr.x, p.x), max(tr.y, p.y));
+            (bl, tr)
+        });
+
+        Rectangle::new(tr.x - bl.x + 1, tr.y - bl.y + 1)
+    }
+
+    /// Checks whether this rectangle fits inside `container`, rotating it if necessary and
+    /// `allow_rotation` is set.
+    pub fn fits_in(&self, container: &Rectangle, allow_rotation: bool) -> bool {
+        let fits = |w, h| w <= container.width && h <= container.height;
+        fits(self.width, self.height) || (allow_rotation && fits(self.height, self.width))
+    }
+
+    pub const fn new(width: u32, height: u32) -> Rectangle {
         Rectangle { width, height }
     }
+
+    /// Like `new`, but fails if either dimension is zero or the area does not fit in a `u64`.
+    pub fn try_new(width: u32, height: u32) -> Result<Rectangle, Error> {
+        if width == 0 || height == 0 {
+            bail!(
+                "Rectangle dimensions must be positive: {} {}",
+                width,
+                height
+            )
+        }
+
+        if (width as u64).checked_mul(height as u64).is_none() {
+            bail!("Rectangle area overflows: {} {}", width, height)
+        }
+
+        Ok(Rectangle { width, height })
+    }
 }
 
 enum Cut {
@@ -104,6 +326,19 @@ enum Cut {
     Vertical(u32),
 }
 
+/// The shortest length a piece `across` wide can be cut to, keeping an area of at least
+/// `min_area`.
+fn min_side(min_area: u64, across: u32) -> u32 {
+    use std::cmp::min;
+
+    let across = u64::from(across.max(1));
+    min(
+        (min_area + across - 1) / across,
+        u64::from(u32::max_value()),
+    )
+    .max(1) as u32
+}
+
 impl fmt::Display for Rectangle {
     //noinspection RsTypeCheck
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
@@ -115,8 +350,14 @@ impl FromStr for Rectangle {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        let dimension = |token: &str| {
+            token
+                .parse::<u32>()
+                .map_err(|_| format_err!("Invalid dimension '{}'", token))
+        };
+
         let result = match s.split_whitespace().collect::<Vec<&str>>().as_slice() {
-            [width, height] => Rectangle::new(width.parse()?, height.parse()?),
+            [width, height] => Rectangle::try_new(dimension(width)?, dimension(height)?)?,
             _ => bail!("Invalid format: {}", s),
         };
 
@@ -171,17 +412,158 @@ impl Placement {
         }
     }
 
+    /// The width this placement takes up on the grid, which is the height of the rectangle
+    /// if it is rotated.
+    pub fn width(&self) -> u32 {
+        match self.rotation {
+            Normal => self.rectangle.width,
+            Rotated => self.rectangle.height,
+        }
+    }
+
+    /// The height this placement takes up on the grid, see `width`.
+    pub fn height(&self) -> u32 {
+        match self.rotation {
+            Normal => self.rectangle.height,
+            Rotated => self.rectangle.width,
+        }
+    }
+
+    /// The area covered by this placement, which is that of its rectangle in either
+    /// orientation.
+    pub fn area(&self) -> u64 {
+        self.rectangle.area()
+    }
+
     pub fn overlaps(&self, rhs: &Placement) -> bool {
         rhs.bottom_left.y <= self.top_right.y
             && rhs.bottom_left.x <= self.top_right.x
             && self.bottom_left.y <= rhs.top_right.y
             && self.bottom_left.x <= rhs.top_right.x
     }
+
+    /// Checks whether fewer than `margin` units separate this placement from `rhs`, in other
+    /// words whether they overlap once both are expanded by `margin`. A margin of 0 is the
+    /// same as `overlaps`.
+    pub fn within_margin(&self, rhs: &Placement, margin: u32) -> bool {
+        rhs.bottom_left.y <= self.top_right.y.saturating_add(margin)
+            && rhs.bottom_left.x <= self.top_right.x.saturating_add(margin)
+            && self.bottom_left.y <= rhs.top_right.y.saturating_add(margin)
+            && self.bottom_left.x <= rhs.top_right.x.saturating_add(margin)
+    }
+
+    /// Checks whether this placement overlaps `rhs` by more than `tolerance` units in both
+    /// directions. A tolerance of 0 is the same as `overlaps`.
+    pub fn overlaps_beyond(&self, rhs: &Placement, tolerance: u32) -> bool {
+        use std::cmp::{max, min};
+
+        let depth = |coord: fn(&Point) -> u32| {
+            let lo = max(coord(&self.bottom_left), coord(&rhs.bottom_left));
+            let hi = min(coord(&self.top_right), coord(&rhs.top_right));
+            (i64::from(hi) - i64::from(lo) + 1).max(0) as u32
+        };
+
+        depth(|p| p.x) > tolerance && depth(|p| p.y) > tolerance
+    }
+
+    /// Checks whether this placement overlaps `rhs` or shares part of an edge with it.
+    /// Placements that only meet at a corner do not touch.
+    pub fn touches(&self, rhs: &Placement) -> bool {
+        let x_overlap =
+            rhs.bottom_left.x <= self.top_right.x && self.bottom_left.x <= rhs.top_right.x;
+        let y_overlap =
+            rhs.bottom_left.y <= self.top_right.y && self.bottom_left.y <= rhs.top_right.y;
+        let x_adjacent =
+            rhs.bottom_left.x <= self.top_right.x + 1 && self.bottom_left.x <= rhs.top_right.x + 1;
+        let y_adjacent =
+            rhs.bottom_left.y <= self.top_right.y + 1 && self.bottom_left.y <= rhs.top_right.y + 1;
+
+        (x_overlap && y_adjacent) || (y_overlap && x_adjacent)
+    }
+
+    /// Checks whether this placement shares part of an edge with `rhs` without overlapping it.
+    pub fn abuts(&self, rhs: &Placement) -> bool {
+        self.touches(rhs) && !self.overlaps(rhs)
+    }
+
+    /// The length of the edge this placement shares with `rhs`, which is 0 unless they abut.
+    pub fn shared_edge(&self, rhs: &Placement) -> u32 {
+        use std::cmp::{max, min};
+
+        if !self.abuts(rhs) {
+            return 0;
+        }
+
+        let span = |coord: fn(&Point) -> u32| {
+            let lo = max(coord(&self.bottom_left), coord(&rhs.bottom_left));
+            let hi = min(coord(&self.top_right), coord(&rhs.top_right));
+            hi + 1 - lo
+        };
+
+        if self.top_right.x + 1 == rhs.bottom_left.x || rhs.top_right.x + 1 == self.bottom_left.x {
+            span(|p| p.y)
+        } else {
+            span(|p| p.x)
+        }
+    }
+
+    /// The area covered by both this placement and `rhs`.
+    pub fn intersection_area(&self, rhs: &Placement) -> u64 {
+        use std::cmp::{max, min};
+
+        if !self.overlaps(rhs) {
+            return 0;
+        }
+
+        let width =
+            min(self.top_right.x, rhs.top_right.x) - max(self.bottom_left.x, rhs.bottom_left.x);
+        let height =
+            min(self.top_right.y, rhs.top_right.y) - max(self.bottom_left.y, rhs.bottom_left.y);
+        (width as u64 + 1) * (height as u64 + 1)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use rand::seq;
+
+    #[test]
+    fn bounding_of() {
+        let points = vec![Point::new(3, 7), Point::new(5, 2), Point::new(4, 4)];
+        assert_eq!(Rectangle::bounding_of(points), Rectangle::new(3, 6));
+        assert_eq!(
+            Rectangle::bounding_of(Some(Point::new(9, 9))),
+            Rectangle::new(1, 1)
+        );
+        assert_eq!(Rectangle::bounding_of(vec![]), Rectangle::new(0, 0));
+    }
+
+    #[test]
+    fn const_constructors() {
+        const RECT: Rectangle = Rectangle::new(3, 4);
+        const ORIGIN: Point = Point::new(0, 0);
+        const AREA: u64 = RECT.area();
+
+        assert_eq!(AREA, 12);
+        assert_eq!(
+            Placement::new(RECT, Normal, ORIGIN).top_right,
+            Point::new(2, 3)
+        );
+    }
+
+    #[test]
+    fn placement_dimensions() {
+        let r = Rectangle::new(3, 5);
+        let normal = Placement::new(r, Normal, Point::new(2, 1));
+        let rotated = Placement::new(r, Rotated, Point::new(2, 1));
+
+        assert_eq!((normal.width(), normal.height()), (3, 5));
+        assert_eq!((rotated.width(), rotated.height()), (5, 3));
+        assert_eq!(rotated.top_right, Point::new(6, 3));
+        assert_eq!(normal.area(), 15);
+        assert_eq!(rotated.area(), 15);
+    }
 
     #[test]
     fn overlap_detection() {
@@ -189,4 +571,107 @@ mod test {
         let p2 = Placement::new(Rectangle::new(5, 5), Rotation::Normal, Point::new(3, 3));
         assert!(p1.overlaps(&p2))
     }
+
+    #[test]
+    fn within_margin() {
+        let r = Rectangle::new(5, 5);
+        let p1 = Placement::new(r, Normal, Point::new(0, 0));
+        let p2 = Placement::new(r, Normal, Point::new(7, 0));
+        assert!(!p1.within_margin(&p2, 2));
+        assert!(p1.within_margin(&p2, 3));
+
+        // Near the end of the coordinate space the expanded placements cannot overflow
+        let max = u32::max_value();
+        let p3 = Placement::new(r, Normal, Point::new(max - 5, max - 5));
+        let p4 = Placement::new(r, Normal, Point::new(max - 13, max - 5));
+        assert!(!p3.within_margin(&p4, 3));
+        assert!(p3.within_margin(&p4, 4));
+        assert!(p3.within_margin(&p3, 10));
+    }
+
+    #[test]
+    fn try_new() {
+        assert_eq!(Rectangle::try_new(3, 4).unwrap(), Rectangle::new(3, 4));
+        assert!(Rectangle::try_new(0, 4).is_err());
+        assert!(Rectangle::try_new(3, 0).is_err());
+        assert!("0 4".parse::<Rectangle>().is_err());
+    }
+
+    #[test]
+    fn fits_in() {
+        let container = Rectangle::new(10, 5);
+
+        assert!(Rectangle::new(8, 5).fits_in(&container, false));
+        assert!(Rectangle::new(8, 5).fits_in(&container, true));
+        assert!(!Rectangle::new(5, 8).fits_in(&container, false));
+        assert!(Rectangle::new(5, 8).fits_in(&container, true));
+        assert!(!Rectangle::new(6, 11).fits_in(&container, true));
+    }
+
+    #[test]
+    fn split_into() {
+        let r = Rectangle::new(10, 10);
+        let pieces = r.split_into(7, &mut rand::thread_rng());
+
+        assert_eq!(pieces.len(), 7);
+        assert_eq!(pieces.iter().map(Rectangle::area).sum::<u64>(), r.area());
+        assert_eq!(
+            Rectangle::new(2, 2)
+                .split_into(9, &mut rand::thread_rng())
+                .len(),
+            4
+        );
+    }
+
+    /// The splitting loop as it was before it split pieces in place, to compare against.
+    fn split_into_reference<R: Rng>(r: Rectangle, k: usize, rng: &mut R) -> Vec<Rectangle> {
+        let mut rectangles = vec![r];
+
+        while rectangles.len() < k {
+            let i = seq::sample_indices(rng, rectangles.len(), 1)[0];
+            let r = rectangles.swap_remove(i);
+
+            if r.width > 1 || r.height > 1 {
+                let (r1, r2) = r.rsplit(rng);
+                rectangles.push(r1);
+                rectangles.push(r2);
+            } else {
+                rectangles.push(r);
+            }
+        }
+
+        rectangles
+    }
+
+    #[test]
+    fn split_into_distribution() {
+        const RUNS: usize = 1000;
+
+        let r = Rectangle::new(100, 100);
+        let mut rng = rand::thread_rng();
+        let stats = |pieces: Vec<Rectangle>| {
+            let largest = pieces.iter().map(Rectangle::area).max().unwrap() as f32;
+            let aspect = pieces.iter().map(Rectangle::aspect_ratio).sum::<f32>();
+            (largest, aspect / pieces.len() as f32)
+        };
+
+        let (mut largest, mut aspect) = (0., 0.);
+        let (mut reference_largest, mut reference_aspect) = (0., 0.);
+        for _ in 0..RUNS {
+            let pieces = r.split_into(50, &mut rng);
+            assert_eq!(pieces.len(), 50);
+            assert_eq!(pieces.iter().map(Rectangle::area).sum::<u64>(), r.area());
+
+            let (l, a) = stats(pieces);
+            largest += l;
+            aspect += a;
+
+            let (l, a) = stats(split_into_reference(r, 50, &mut rng));
+            reference_largest += l;
+            reference_aspect += a;
+        }
+
+        assert!((largest / reference_largest - 1.).abs() < 0.15);
+        assert!((aspect / reference_aspect - 1.).abs() < 0.1);
+    }
 }
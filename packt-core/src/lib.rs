@@ -1,8 +1,15 @@
 #[macro_use]
 extern crate failure;
+#[macro_use]
+extern crate log;
 extern crate crossbeam_channel;
+#[macro_use]
+extern crate lazy_static;
+#[cfg(unix)]
+extern crate libc;
 extern crate rand;
 extern crate serde;
+extern crate serde_json;
 extern crate tokio;
 extern crate tokio_core;
 extern crate tokio_io;
@@ -14,3 +21,5 @@ pub mod geometry;
 pub mod problem;
 pub mod runner;
 pub mod solution;
+pub mod solver;
+pub mod viz;
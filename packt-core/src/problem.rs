@@ -1,17 +1,19 @@
 use failure::Error;
-use geometry::Rectangle;
+use geometry::{Placement, Point, Rectangle, Rotation};
 use rand::{self, seq, Rng};
-use std::cmp::min;
+use solution::Solution;
+use std::cmp::{min, Reverse};
 use std::fmt;
 use std::fmt::Formatter;
 use std::fs::File;
 use std::fs::OpenOptions;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::str::FromStr;
 
 const N_DEFAULTS: [usize; 5] = [3, 5, 10, 25, 5000];
 const AVG_RECTANGLE_AREA: u64 = 50;
+const MAX_GENERATE_ATTEMPTS: usize = 100;
 
 pub fn generate(n: usize, variant: Option<Variant>, allow_rotation: Option<bool>) -> Problem {
     use rand::distributions::{IndependentSample, Range};
@@ -70,6 +72,9 @@ pub fn generate(n: usize, variant: Option<Variant>, allow_rotation: Option<bool>
         allow_rotation,
         rectangles,
         source: None,
+        name: None,
+        truncated: false,
+        palette: None,
     }
 }
 
@@ -77,8 +82,14 @@ pub fn generate(n: usize, variant: Option<Variant>, allow_rotation: Option<bool>
 pub struct Problem {
     pub variant: Variant,
     pub allow_rotation: bool,
+    /// Prefer `rectangles()` and `rectangle_count()` over accessing this field directly.
     pub rectangles: Vec<Rectangle>,
     pub source: Option<Rectangle>,
+    pub name: Option<String>,
+    /// Whether rectangles were left out when loading this problem.
+    pub truncated: bool,
+    /// The sizes all rectangles were drawn from, if generated from a palette.
+    pub palette: Option<Vec<Rectangle>>,
 }
 
 impl Problem {
@@ -93,41 +104,251 @@ impl Problem {
                 allow_rotation,
                 rectangles,
                 source: None,
+                name: None,
+                truncated: false,
+                palette: None,
             };
         }
 
-        let mut rng = rand::thread_rng();
-        let mut rectangles = Vec::with_capacity(n as usize);
-        rectangles.push(r);
-
-        while rectangles.len() < n {
-            let i = seq::sample_indices(&mut rng, rectangles.len(), 1)[0];
-            let r = rectangles.swap_remove(i);
-
-            if r.width > 1 || r.height > 1 {
-                let (r1, r2) = r.simple_rsplit();
-                rectangles.push(r1);
-                rectangles.push(r2);
-            } else {
-                rectangles.push(r);
-            }
-        }
+        let rectangles = r.split_into(n, &mut rand::thread_rng());
 
         Problem {
             variant: v,
             allow_rotation,
             rectangles,
             source: Some(r),
+            name: None,
+            truncated: false,
+            palette: None,
+        }
+    }
+
+    /// The rectangles to pack, in input order.
+    pub fn rectangles(&self) -> &[Rectangle] {
+        &self.rectangles
+    }
+
+    pub fn rectangle_count(&self) -> usize {
+        self.rectangles.len()
+    }
+
+    /// Appends `r` to the rectangles to pack.
+    ///
+    /// The problem can no longer be packed perfectly into its source, so the source is
+    /// cleared.
+    pub fn add_rectangle(&mut self, r: Rectangle) {
+        self.rectangles.push(r);
+        self.source = None;
+    }
+
+    /// Removes and returns the rectangle at `index`, or `None` if there is no such rectangle.
+    ///
+    /// Like `add_rectangle`, this clears the source if a rectangle is removed.
+    pub fn remove_rectangle(&mut self, index: usize) -> Option<Rectangle> {
+        if index >= self.rectangles.len() {
+            return None;
+        }
+
+        self.source = None;
+        Some(self.rectangles.remove(index))
+    }
+
+    /// Mutable access to the rectangles, for preprocessing them in place.
+    ///
+    /// The number of rectangles cannot change this way, see `add_rectangle` and
+    /// `remove_rectangle` for that. Reordering rectangles keeps the problem the same, but
+    /// resizing them does not, so clear the source when doing so.
+    pub fn rectangles_mut(&mut self) -> &mut [Rectangle] {
+        &mut self.rectangles
+    }
+
+    /// Sorts the rectangles by decreasing area, keeping the input order of equal areas.
+    pub fn sort_by_area(&mut self) {
+        self.rectangles_mut().sort_by_key(|r| Reverse(r.area()));
+    }
+
+    /// Puts the rectangles in a random order.
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        rng.shuffle(self.rectangles_mut());
+    }
+
+    /// Multiplies every dimension by `factor`, including the fixed height, the source and the
+    /// palette, so the scaled problem can be packed just like the original.
+    ///
+    /// Fails without changing the problem if a dimension would overflow.
+    pub fn scale(&mut self, factor: u32) -> Result<(), Error> {
+        let scale = |x: u32| {
+            x.checked_mul(factor)
+                .ok_or_else(|| format_err!("Scaling {} by {} overflows", x, factor))
+        };
+        let scaled = |r: &Rectangle| -> Result<Rectangle, Error> {
+            Ok(Rectangle::new(scale(r.width)?, scale(r.height)?))
+        };
+        let scaled_all = |rs: &[Rectangle]| rs.iter().map(&scaled).collect::<Result<Vec<_>, _>>();
+
+        let rectangles = scaled_all(&self.rectangles)?;
+        let variant = match self.variant {
+            Variant::Fixed(k) => Variant::Fixed(scale(k)?),
+            v => v,
+        };
+        let source = match self.source {
+            Some(ref r) => Some(scaled(r)?),
+            None => None,
+        };
+        let palette = match self.palette {
+            Some(ref palette) => Some(scaled_all(palette)?),
+            None => None,
+        };
+
+        self.rectangles = rectangles;
+        self.variant = variant;
+        self.source = source;
+        self.palette = palette;
+        Ok(())
+    }
+
+    /// A copy of this problem with rotations allowed or not, e.g. to find out whether
+    /// allowing rotations gives a better packing. Everything else, including the source, is
+    /// kept.
+    pub fn with_rotation(&self, allow: bool) -> Problem {
+        Problem {
+            allow_rotation: allow,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this problem with a few rectangles, about one in ten, resized by up to
+    /// `magnitude` in either dimension, to generate a family of instances around this one.
+    ///
+    /// The number of rectangles, the variant and the rotation flag are kept, and dimensions
+    /// stay positive and within a fixed height, which with rotations only limits the shorter
+    /// side. Since the rectangles no longer add up to the
+    /// source, the copy is generally not perfectly packable, and its source is cleared.
+    pub fn perturb<R: Rng>(&self, rng: &mut R, magnitude: u32) -> Problem {
+        let mut problem = Problem {
+            source: None,
+            ..self.clone()
+        };
+        if magnitude == 0 || problem.rectangles.is_empty() {
+            return problem;
+        }
+
+        let fixed_height = self.fixed_height().unwrap_or_else(u32::max_value);
+        let max_height = if self.allow_rotation {
+            u32::max_value()
+        } else {
+            fixed_height
+        };
+        let nudge = |x: u32, max: u32, rng: &mut R| {
+            let delta = rng.gen_range(1, i64::from(magnitude) + 1);
+            let delta = if rng.gen() { delta } else { -delta };
+            (i64::from(x) + delta).max(1).min(i64::from(max)) as u32
+        };
+
+        let n = problem.rectangles.len();
+        for _ in 0..(n / 10).max(1) {
+            let i = rng.gen_range(0, n);
+            let Rectangle { width, height } = problem.rectangles[i];
+            let width = nudge(width, u32::max_value(), rng);
+            let height = nudge(height, max_height, rng);
+
+            // A rotated rectangle still fits as long as its shorter side does
+            problem.rectangles[i] = if width < height {
+                Rectangle::new(width.min(fixed_height), height)
+            } else {
+                Rectangle::new(width, height.min(fixed_height))
+            };
+        }
+
+        problem
+    }
+
+    /// The number of distinct `(width, height)` sizes among the rectangles.
+    pub fn distinct_sizes(&self) -> usize {
+        let mut sizes: Vec<(u32, u32)> = self
+            .rectangles
+            .iter()
+            .map(|r| (r.width, r.height))
+            .collect();
+        sizes.sort();
+        sizes.dedup();
+        sizes.len()
+    }
+
+    /// The fewest `bin`-sized bins that could hold all rectangles, judging by area alone.
+    ///
+    /// This is a lower bound for bin packing: a packing into `k` bins is at most
+    /// `k / min_bins` times worse than optimal. Fails if `bin` has no area.
+    pub fn min_bins(&self, bin: Rectangle) -> Result<usize, Error> {
+        if bin.area() == 0 {
+            bail!(
+                "Cannot fit rectangles into an empty {}x{} bin",
+                bin.width,
+                bin.height
+            );
         }
+
+        let total: u64 = self.rectangles.iter().map(Rectangle::area).sum();
+        Ok((total / bin.area() + (total % bin.area()).min(1)) as usize)
     }
 
     fn config_str(&self) -> String {
-        format!(
+        let config = format!(
             "container height: {v}\nrotations allowed: {r}\nnumber of rectangles: {n}",
             v = self.variant,
             r = if self.allow_rotation { "yes" } else { "no" },
-            n = self.rectangles.len()
-        )
+            n = self.rectangle_count()
+        );
+
+        match self.name {
+            Some(ref name) => format!("name: {}\n{}", name, config),
+            None => config,
+        }
+    }
+
+    /// Combines `problems` into one problem with all of their rectangles, in order.
+    ///
+    /// All problems must have the same variant and rotation setting. The source of the
+    /// combined problem is unknown, so it is cleared.
+    pub fn merge(problems: &[Problem]) -> Result<Problem, Error> {
+        let first = match problems.first() {
+            Some(first) => first,
+            None => bail!("Cannot merge an empty list of problems"),
+        };
+
+        if let Some(p) = problems
+            .iter()
+            .find(|p| p.variant != first.variant || p.allow_rotation != first.allow_rotation)
+        {
+            bail!(
+                "Cannot merge problems with different settings: {} (rotation {}) and {} \
+                 (rotation {})",
+                first.variant,
+                first.allow_rotation,
+                p.variant,
+                p.allow_rotation
+            )
+        }
+
+        Ok(Problem {
+            variant: first.variant,
+            allow_rotation: first.allow_rotation,
+            rectangles: problems
+                .iter()
+                .flat_map(|p| p.rectangles.iter().cloned())
+                .collect(),
+            source: None,
+            name: None,
+            truncated: problems.iter().any(|p| p.truncated),
+            palette: None,
+        })
+    }
+
+    /// Checks whether `other` describes the same packing instance, ignoring its name.
+    pub fn same_instance(&self, other: &Problem) -> bool {
+        self.variant == other.variant
+            && self.allow_rotation == other.allow_rotation
+            && self.rectangles == other.rectangles
     }
 
     pub fn digest(&self) -> String {
@@ -137,6 +358,11 @@ impl Problem {
             config.push_str(&format!("\nbounding box: {}", source.to_string()));
         }
 
+        if let Some(ref palette) = self.palette {
+            let sizes: Vec<String> = palette.iter().map(Rectangle::to_string).collect();
+            config.push_str(&format!("\npalette: {}", sizes.join(", ")));
+        }
+
         self.rectangles
             .iter()
             .for_each(|r| config.push_str(&format!("\n{}", r.to_string())));
@@ -144,10 +370,113 @@ impl Problem {
         config
     }
 
+    /// The height of the container, if it is fixed.
+    pub fn fixed_height(&self) -> Option<u32> {
+        self.variant.fixed_height()
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.variant == Variant::Free
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        !self.is_free()
+    }
+
+    /// Fails if this problem provably has no solution, which is the case when some rectangle
+    /// does not fit within the fixed height in any allowed orientation.
+    pub fn feasible(&self) -> Result<(), Error> {
+        if let Some(k) = self.fixed_height() {
+            let strip = Rectangle::new(u32::max_value(), k);
+            let fits = |r: &Rectangle| r.fits_in(&strip, self.allow_rotation);
+            if let Some(r) = self.rectangles.iter().find(|r| !fits(r)) {
+                bail!("{:?} does not fit within the fixed height {}", r, k)
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Packs the rectangles of this problem with a greedy bottom-left heuristic, largest first.
+    ///
+    /// Every rectangle is put at the leftmost position along the skyline of the packing so
+    /// far, trying both orientations if rotation is allowed. Free problems are packed into a
+    /// roughly square strip. The result is valid, but usually far from optimal.
+    pub fn greedy_bottom_left(&self) -> Result<Solution, Error> {
+        self.feasible()?;
+
+        let height = match self.variant {
+            Variant::Fixed(k) => k,
+            Variant::Free => {
+                let area: u64 = self.rectangles.iter().map(Rectangle::area).sum();
+                let tallest = self
+                    .rectangles
+                    .iter()
+                    .map(|r| {
+                        if self.allow_rotation {
+                            r.width.min(r.height)
+                        } else {
+                            r.height
+                        }
+                    })
+                    .max()
+                    .unwrap_or(0);
+
+                tallest.max((area as f64).sqrt().ceil() as u32)
+            }
+        };
+
+        let mut order: Vec<usize> = (0..self.rectangles.len()).collect();
+        order.sort_by_key(|&i| Reverse(self.rectangles[i].area()));
+
+        let mut skyline = Skyline::new(height);
+        let mut placements = vec![None; self.rectangles.len()];
+        for i in order {
+            let r = self.rectangles[i];
+            let mut options = vec![(Rotation::Normal, r.width, r.height)];
+            if self.allow_rotation && r.width != r.height {
+                options.push((Rotation::Rotated, r.height, r.width));
+            }
+
+            let (rotation, coord, w, h) = options
+                .into_iter()
+                .filter_map(|(rotation, w, h)| {
+                    let coord = skyline.leftmost_position(h)?;
+                    Some((rotation, coord, w, h))
+                })
+                .min_by_key(|&(_, coord, w, _)| (coord.x + w, coord.y))
+                .ok_or_else(|| format_err!("{:?} does not fit within the height {}", r, height))?;
+
+            skyline.raise(coord.y, h, coord.x + w);
+            placements[i] = Some(Placement::new(r, rotation, coord));
+        }
+
+        let placements = placements.into_iter().map(Option::unwrap).collect();
+        let solution = Solution::new(self.clone(), placements);
+        if !solution.is_valid() {
+            bail!("Greedy packing produced overlapping placements")
+        }
+
+        Ok(solution)
+    }
+
     pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut file = OpenOptions::new().write(true).create(true).open(path)?;
+        let file = OpenOptions::new().write(true).create(true).open(path)?;
+        let mut writer = BufWriter::new(file);
 
-        file.write_all(self.to_string().as_bytes())
+        self.to_writer(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes this problem to `w` line by line, in the same format as `Display`, without
+    /// building the whole text in memory first.
+    pub fn to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "{}", self.config_str())?;
+        for r in &self.rectangles {
+            write!(w, "\n{}", r)?;
+        }
+
+        Ok(())
     }
 
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Problem, Error> {
@@ -155,17 +484,120 @@ impl Problem {
         File::open(path)?.read_to_string(&mut content)?;
         content.parse()
     }
+
+    /// Loads the problem at `path`, keeping only its first `max` rectangles.
+    ///
+    /// Reading stops as soon as `max` rectangles have been read, so this is cheap even for huge
+    /// files. If any rectangles were left out, the problem is marked as truncated.
+    pub fn from_path_limited<P: AsRef<Path>>(path: P, max: usize) -> Result<Problem, Error> {
+        const HEADERS: [&str; 5] = [
+            "name:",
+            "container height:",
+            "rotations allowed:",
+            "number of rectangles:",
+            "total area:",
+        ];
+
+        let mut content = String::new();
+        let mut count = 0;
+        let mut truncated = false;
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let trimmed = line.trim();
+            let is_rectangle = !trimmed.is_empty()
+                && !trimmed.starts_with('#')
+                && !HEADERS.iter().any(|h| trimmed.starts_with(h));
+
+            if is_rectangle {
+                if count == max {
+                    truncated = true;
+                    break;
+                }
+                count += 1;
+            }
+
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        let mut problem: Problem = content.parse()?;
+        problem.truncated = truncated;
+        Ok(problem)
+    }
 }
 
-impl fmt::Display for Problem {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let mut s = self.config_str();
+/// The first free x-coordinate of every row of a strip, stored as `(y, x)` steps that each
+/// hold from row `y` up to the next step, so its size does not depend on the height.
+struct Skyline {
+    height: u32,
+    steps: Vec<(u32, u32)>,
+}
 
-        self.rectangles
+impl Skyline {
+    fn new(height: u32) -> Skyline {
+        Skyline {
+            height,
+            steps: vec![(0, 0)],
+        }
+    }
+
+    /// Finds the bottom-left point where a rectangle of height `h` can be placed against this
+    /// skyline, the lowest of the leftmost ones. `None` if the strip is lower than `h`.
+    ///
+    /// Only the bottoms of steps need to be tried, as moving a rectangle down to the bottom
+    /// of the step it starts in never moves it to the right.
+    fn leftmost_position(&self, h: u32) -> Option<Point> {
+        let mut best: Option<Point> = None;
+        for (i, &(y, _)) in self.steps.iter().enumerate() {
+            if u64::from(y) + u64::from(h) > u64::from(self.height) {
+                break;
+            }
+
+            let x = self.steps[i..]
+                .iter()
+                .take_while(|&&(step, _)| step < y + h)
+                .map(|&(_, x)| x)
+                .max()
+                .unwrap_or(0);
+            if best.map_or(true, |best| x < best.x) {
+                best = Some(Point::new(x, y));
+            }
+        }
+
+        best
+    }
+
+    /// Moves the first free x-coordinate of the rows from `y` up to `y + h` to `x`.
+    fn raise(&mut self, y: u32, h: u32, x: u32) {
+        let top = y + h;
+        let above = self
+            .steps
             .iter()
-            .for_each(|r| s.push_str(&format!("\n{}", r.to_string())));
+            .take_while(|&&(step, _)| step <= top)
+            .last()
+            .map_or(0, |&(_, x)| x);
 
-        write!(f, "{}", s)
+        self.steps.retain(|&(step, _)| step < y || step >= top);
+        let i = self
+            .steps
+            .iter()
+            .position(|&(step, _)| step > y)
+            .unwrap_or(self.steps.len());
+        self.steps.insert(i, (y, x));
+        if top < self.height && self.steps.get(i + 1).map_or(true, |&(step, _)| step != top) {
+            self.steps.insert(i + 1, (top, above));
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.config_str())?;
+        for r in &self.rectangles {
+            write!(f, "\n{}", r)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -173,7 +605,46 @@ impl FromStr for Problem {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
-        let mut lines = s.trim().lines();
+        Problem::parse(s).map(|(problem, _)| problem)
+    }
+}
+
+impl Problem {
+    /// Like `from_str`, but also fails if the number of rectangles differs from the declared
+    /// `number of rectangles`. The error previews the first and last few rectangles that
+    /// were parsed, to tell a truncated file from one with junk lines.
+    ///
+    /// `from_str` does not check this, so that truncated problems can still be loaded.
+    pub fn from_str_strict(s: &str) -> Result<Problem, Error> {
+        let (problem, declared) = Problem::parse(s)?;
+        let n = problem.rectangle_count();
+        match declared {
+            Some(declared) if declared != n => bail!(
+                "Expected {} rectangles, but parsed {}: {}",
+                declared,
+                n,
+                preview(&problem.rectangles)
+            ),
+            _ => Ok(problem),
+        }
+    }
+
+    /// Parses a problem, along with the number of rectangles it declares.
+    fn parse(s: &str) -> Result<(Problem, Option<usize>), Error> {
+        let mut lines = s
+            .trim()
+            .lines()
+            .filter(|l| !l.trim().starts_with('#'))
+            .peekable();
+
+        let name = match lines.peek() {
+            Some(l) if l.starts_with("name:") => Some(l["name:".len()..].trim().to_string()),
+            _ => None,
+        };
+        if name.is_some() {
+            lines.next();
+        }
+
         let l1: Vec<&str> = lines
             .next()
             .ok_or_else(|| format_err!("Unexpected end of file: unable to parse problem variant"))?
@@ -196,17 +667,76 @@ impl FromStr for Problem {
             _ => bail!("Invalid format: {}", l2),
         };
 
-        lines.next();
+        let declared = match lines.next() {
+            Some(l) if l.starts_with("number of rectangles:") => l["number of rectangles:".len()..]
+                .trim()
+                .parse::<usize>()
+                .ok(),
+            _ => None,
+        };
+        let mut lines: Vec<&str> = lines.collect();
+        let total_area = match lines.last() {
+            Some(l) if l.starts_with("total area:") => {
+                Some(l["total area:".len()..].trim().parse::<u64>()?)
+            }
+            _ => None,
+        };
+        if total_area.is_some() {
+            lines.pop();
+        }
+
         let rectangles = lines
-            .map(|s| s.parse())
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| {
+                s.parse()
+                    .map_err(|e| format_err!("{} on rectangle line {}", e, i + 1))
+            })
             .collect::<Result<Vec<Rectangle>, _>>()?;
 
-        Ok(Problem {
+        if let Some(expected) = total_area {
+            let actual: u64 = rectangles.iter().map(Rectangle::area).sum();
+            if actual != expected {
+                bail!(
+                    "Total area of rectangles is {}, but the file declares {}",
+                    actual,
+                    expected
+                );
+            }
+        }
+
+        let problem = Problem {
             variant,
             allow_rotation,
             rectangles,
             source: None,
-        })
+            name,
+            truncated: false,
+            palette: None,
+        };
+
+        Ok((problem, declared))
+    }
+}
+
+/// Lists the first and last 3 rectangles, or all of them if there are at most 6.
+fn preview(rectangles: &[Rectangle]) -> String {
+    let show = |rs: &[Rectangle]| {
+        rs.iter()
+            .map(Rectangle::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if rectangles.len() <= 6 {
+        show(rectangles)
+    } else {
+        let n = rectangles.len();
+        format!(
+            "{}, ..., {}",
+            show(&rectangles[..3]),
+            show(&rectangles[n - 3..])
+        )
     }
 }
 
@@ -216,6 +746,15 @@ pub struct Generator {
     rectangles: Option<usize>,
     variant: Option<Variant>,
     allow_rotation: Option<bool>,
+    max_aspect_ratio: Option<f32>,
+    min_area: Option<u64>,
+    min_distinct_sizes: Option<usize>,
+    container_ratio: Option<(u32, u32)>,
+    container_aspect: Option<f32>,
+    default_counts: Option<Vec<usize>>,
+    palette: Option<Vec<Rectangle>>,
+    shuffle: bool,
+    sample: Option<SizeDistribution>,
 }
 
 impl Generator {
@@ -223,16 +762,87 @@ impl Generator {
         Self::default()
     }
 
+    /// Creates a generator that draws rectangles whose widths and heights follow log-normal
+    /// distributions fitted to `sample`, to mimic a real workload.
+    ///
+    /// The rectangles are drawn independently instead of by splitting a container, so the
+    /// problems are generally not perfectly packable and have no source. A fixed variant
+    /// gets the height of the tallest rectangle, and the container settings are ignored. An
+    /// empty sample gives the usual generator.
+    pub fn from_sample(sample: &[Rectangle]) -> Self {
+        Generator {
+            sample: SizeDistribution::fit(sample),
+            ..Self::default()
+        }
+    }
+
+    /// Generates a problem, retrying to meet the `min_distinct_sizes` floor if one is set.
+    ///
+    /// If no attempt meets the floor, the one with the most distinct sizes is returned. Use
+    /// `try_generate` to get an error instead.
     pub fn generate(&self) -> Problem {
+        self.attempt().unwrap_or_else(|best| best)
+    }
+
+    /// Like `generate`, but fails if no attempt meets the `min_distinct_sizes` floor.
+    pub fn try_generate(&self) -> Result<Problem, Error> {
+        self.attempt().map_err(|best| {
+            format_err!(
+                "Failed to generate a problem with at least {} distinct sizes in {} attempts, \
+                 the best one had {}",
+                self.min_distinct_sizes.unwrap_or(0),
+                MAX_GENERATE_ATTEMPTS,
+                best.distinct_sizes()
+            )
+        })
+    }
+
+    /// Generates problems until one meets the `min_distinct_sizes` floor, returning the best
+    /// one as an error if none does.
+    fn attempt(&self) -> Result<Problem, Problem> {
+        let floor = self.min_distinct_sizes.unwrap_or(0);
+        let mut best = self.generate_once();
+
+        for _ in 1..MAX_GENERATE_ATTEMPTS {
+            if best.distinct_sizes() >= floor {
+                break;
+            }
+
+            let problem = self.generate_once();
+            if problem.distinct_sizes() > best.distinct_sizes() {
+                best = problem;
+            }
+        }
+
+        if best.distinct_sizes() >= floor {
+            Ok(best)
+        } else {
+            Err(best)
+        }
+    }
+
+    fn generate_once(&self) -> Problem {
         let mut rng = rand::thread_rng();
+        let defaults = match self.default_counts {
+            Some(ref counts) if !counts.is_empty() => counts.as_slice(),
+            _ => &N_DEFAULTS,
+        };
         let mut n = self
             .rectangles
-            .unwrap_or_else(|| seq::sample_slice(&mut rng, &N_DEFAULTS, 1)[0]);
+            .unwrap_or_else(|| seq::sample_slice(&mut rng, defaults, 1)[0]);
+
+        if let Some(ref distribution) = self.sample {
+            return self.generate_sampled(distribution, n, &mut rng);
+        }
 
         let r = self.container.unwrap_or_else(|| {
             let area = n as u64 * AVG_RECTANGLE_AREA;
 
-            Rectangle::gen_with_area(area)
+            match (self.container_ratio, self.container_aspect) {
+                (Some((w, h)), _) => Rectangle::gen_with_ratio(area, w, h),
+                (None, Some(target)) => Rectangle::gen_with_aspect(area, target),
+                (None, None) => Rectangle::gen_with_area(area),
+            }
         });
 
         n = min(n, r.area() as usize);
@@ -251,7 +861,90 @@ impl Generator {
             });
 
         let allow_rotation = self.allow_rotation.unwrap_or_else(|| rng.gen());
-        Problem::generate_from(r, n, variant, allow_rotation)
+        if let Some(ref palette) = self.palette {
+            return Problem {
+                variant,
+                allow_rotation,
+                rectangles: tile(r, n, palette, &mut rng),
+                source: None,
+                name: None,
+                truncated: false,
+                palette: Some(palette.clone()),
+            };
+        }
+
+        // Without limits on the pieces or shuffling, the container is simply split up
+        if self.max_aspect_ratio.is_none() && self.min_area.is_none() && !self.shuffle {
+            return Problem::generate_from(r, n, variant, allow_rotation);
+        }
+
+        let rectangles = self.split(r, n, &mut rng);
+
+        Problem {
+            variant,
+            allow_rotation,
+            rectangles,
+            source: Some(r),
+            name: None,
+            truncated: false,
+            palette: None,
+        }
+    }
+
+    fn generate_sampled<R: Rng>(
+        &self,
+        distribution: &SizeDistribution,
+        n: usize,
+        rng: &mut R,
+    ) -> Problem {
+        let rectangles: Vec<Rectangle> = (0..n).map(|_| distribution.sample(rng)).collect();
+        let allow_rotation = self.allow_rotation.unwrap_or_else(|| rng.gen());
+        let height = rectangles
+            .iter()
+            .map(|r| {
+                if allow_rotation {
+                    min(r.width, r.height)
+                } else {
+                    r.height
+                }
+            })
+            .max()
+            .unwrap_or(1);
+
+        let variant = match self.variant {
+            Some(Variant::Fixed(_)) => Variant::Fixed(height),
+            Some(v) => v,
+            None if rng.gen() => Variant::Free,
+            None => Variant::Fixed(height),
+        };
+
+        Problem {
+            variant,
+            allow_rotation,
+            rectangles,
+            source: None,
+            name: None,
+            truncated: false,
+            palette: None,
+        }
+    }
+
+    fn split<R: Rng>(&self, r: Rectangle, n: usize, rng: &mut R) -> Vec<Rectangle> {
+        let mut rectangles = match (self.max_aspect_ratio, self.min_area) {
+            (None, None) => r.split_into(n, rng),
+            (ratio, min_area) => r.split_into_limited(n, ratio, min_area.unwrap_or(1), rng),
+        };
+
+        // The limits may forbid cutting the container at all, which would give it away
+        if n > 1 && rectangles == [r] {
+            rectangles = r.split_into(2, rng);
+        }
+
+        if self.shuffle {
+            rng.shuffle(&mut rectangles);
+        }
+
+        rectangles
     }
 
     pub fn rectangles(&mut self, mut n: usize) {
@@ -262,10 +955,83 @@ impl Generator {
         self.rectangles = Some(n);
     }
 
+    /// Sets the rectangle counts to pick from when no amount of rectangles is given.
+    ///
+    /// An empty slice restores the built-in defaults.
+    pub fn default_counts(&mut self, counts: &[usize]) {
+        self.default_counts = Some(counts.to_vec());
+    }
+
+    /// Draws all rectangles from `palette` instead of splitting the container.
+    ///
+    /// Pieces are laid out on shelves inside the container, which stops once the container is
+    /// full. The resulting problem may therefore have fewer rectangles than requested, and is
+    /// generally not perfectly packable. An empty palette restores the splitting behaviour.
+    pub fn size_palette(&mut self, palette: Vec<Rectangle>) {
+        self.palette = if palette.is_empty() {
+            None
+        } else {
+            Some(palette)
+        };
+    }
+
     pub fn allow_rotation(&mut self, b: bool) {
         self.allow_rotation = Some(b);
     }
 
+    /// Only cut rectangles in ways that keep the aspect ratio of both pieces at most `ratio`.
+    ///
+    /// This may produce fewer rectangles than requested, once no piece can be cut any further.
+    /// As with `min_area`, the container itself is always cut once if more than one rectangle
+    /// is requested.
+    pub fn max_aspect_ratio(&mut self, ratio: f32) {
+        self.max_aspect_ratio = Some(ratio.max(1.));
+    }
+
+    /// Stops cutting rectangles once that would leave a piece with an area below `area`.
+    ///
+    /// Like `max_aspect_ratio`, this may produce fewer rectangles than requested. The pieces
+    /// still fill the container exactly. If more than one rectangle is requested, the
+    /// container itself is always cut once, even if that breaks either limit.
+    pub fn min_area(&mut self, area: u32) {
+        self.min_area = Some(u64::from(area.max(1)));
+    }
+
+    /// Requires at least `n` distinct rectangle sizes, so instances are not trivially made up
+    /// of many identical pieces.
+    ///
+    /// Problems are regenerated a bounded number of times until one meets this floor, see
+    /// `generate` and `try_generate` for what happens if none does.
+    pub fn min_distinct_sizes(&mut self, n: usize) {
+        self.min_distinct_sizes = Some(n);
+    }
+
+    /// Gives generated containers an aspect ratio of roughly `w_ratio:h_ratio`, e.g. 16:9.
+    ///
+    /// Only applies when no container is set. The container area is chosen as usual, but may
+    /// deviate slightly to get closer to the requested ratio.
+    pub fn container_ratio(&mut self, w_ratio: u32, h_ratio: u32) {
+        self.container_ratio = Some((w_ratio, h_ratio));
+    }
+
+    /// Picks the generated container with an aspect ratio (width over height) close to
+    /// `target`, e.g. 4 for a wide strip or 0.25 for a tall one.
+    ///
+    /// Only applies when neither a container nor a `container_ratio` is set. Unlike
+    /// `container_ratio` the area is kept, so how close the container gets depends on its
+    /// divisors.
+    pub fn container_aspect(&mut self, target: f32) {
+        self.container_aspect = Some(target);
+    }
+
+    /// Puts the rectangles in a random order, rather than the order in which they were cut.
+    ///
+    /// Without shuffling, the first rectangle is always the one in the bottom-left corner of
+    /// the container, and pieces cut late end up at the back.
+    pub fn shuffle(&mut self, b: bool) {
+        self.shuffle = b;
+    }
+
     pub fn variant(&mut self, v: Variant) {
         self.variant = Some(v);
     }
@@ -276,12 +1042,98 @@ impl Generator {
     }
 }
 
+/// Independent log-normal distributions of rectangle widths and heights, given as the mean
+/// and standard deviation of their logarithms.
+#[derive(Clone, Copy, Debug)]
+struct SizeDistribution {
+    width: (f64, f64),
+    height: (f64, f64),
+}
+
+impl SizeDistribution {
+    /// Fits the distributions to `sample` by maximum likelihood, if it is not empty.
+    fn fit(sample: &[Rectangle]) -> Option<SizeDistribution> {
+        if sample.is_empty() {
+            return None;
+        }
+
+        let fit = |f: fn(&Rectangle) -> u32| {
+            let logs: Vec<f64> = sample.iter().map(|r| f64::from(f(r)).ln()).collect();
+            let mean = logs.iter().sum::<f64>() / logs.len() as f64;
+            let variance =
+                logs.iter().map(|l| (l - mean) * (l - mean)).sum::<f64>() / logs.len() as f64;
+            (mean, variance.sqrt())
+        };
+
+        Some(SizeDistribution {
+            width: fit(|r| r.width),
+            height: fit(|r| r.height),
+        })
+    }
+
+    fn sample<R: Rng>(&self, rng: &mut R) -> Rectangle {
+        use rand::distributions::{IndependentSample, LogNormal};
+
+        let dimension = |(mean, std_dev): (f64, f64), rng: &mut R| {
+            let x = LogNormal::new(mean, std_dev).ind_sample(rng).round();
+            x.max(1.).min(f64::from(u32::max_value())) as u32
+        };
+
+        let width = dimension(self.width, rng);
+        Rectangle::new(width, dimension(self.height, rng))
+    }
+}
+
+/// Lays out up to `n` randomly chosen `palette` pieces on shelves inside `container`.
+fn tile<R: Rng>(
+    container: Rectangle,
+    n: usize,
+    palette: &[Rectangle],
+    rng: &mut R,
+) -> Vec<Rectangle> {
+    let mut rectangles = Vec::with_capacity(n);
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+
+    while rectangles.len() < n {
+        let fitting: Vec<&Rectangle> = palette
+            .iter()
+            .filter(|r| x + r.width <= container.width && y + r.height <= container.height)
+            .collect();
+
+        match rng.choose(&fitting) {
+            Some(&&r) => {
+                rectangles.push(r);
+                x += r.width;
+                shelf = shelf.max(r.height);
+            }
+            None if x > 0 && shelf > 0 => {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+            None => break,
+        }
+    }
+
+    rectangles
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Serialize)]
 pub enum Variant {
     Free,
     Fixed(u32),
 }
 
+impl Variant {
+    /// The height of the container, if it is fixed.
+    pub fn fixed_height(self) -> Option<u32> {
+        match self {
+            Variant::Fixed(k) => Some(k),
+            Variant::Free => None,
+        }
+    }
+}
+
 impl fmt::Display for Variant {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match *self {
@@ -310,6 +1162,7 @@ impl FromStr for Variant {
 mod tests {
     #![allow(non_upper_case_globals)]
     use super::*;
+    use std::env;
     const input: &str =
         "container height: fixed 22\nrotations allowed: no\nnumber of rectangles: 2\n12 8\n10 9";
 
@@ -320,10 +1173,174 @@ mod tests {
             allow_rotation: false,
             rectangles: vec![Rectangle::new(12, 8), Rectangle::new(10, 9)],
             source: None,
+            name: None,
+            truncated: false,
+            palette: None,
         };
 
         let result: Problem = input.parse().unwrap();
         assert_eq!(result, expected);
+        assert_eq!(result.rectangle_count(), 2);
+        assert_eq!(result.rectangles(), &expected.rectangles[..]);
+    }
+
+    #[test]
+    fn invalid_dimensions() {
+        let negative = input.replace("10 9", "-10 9");
+        let error = negative.parse::<Problem>().unwrap_err().to_string();
+        assert_eq!(error, "Invalid dimension '-10' on rectangle line 2");
+
+        let text = input.replace("12 8", "12 eight");
+        let error = text.parse::<Problem>().unwrap_err().to_string();
+        assert_eq!(error, "Invalid dimension 'eight' on rectangle line 1");
+    }
+
+    #[test]
+    fn min_bins() {
+        let mut problem: Problem = input.parse().unwrap();
+        // 12x8 + 10x9 + 4x3 is exactly twice the area of a bin
+        let bin = Rectangle::new(9, 11);
+        problem.add_rectangle(Rectangle::new(4, 3));
+        assert_eq!(problem.min_bins(bin).unwrap(), 2);
+
+        problem.add_rectangle(Rectangle::new(1, 1));
+        assert_eq!(problem.min_bins(bin).unwrap(), 3);
+
+        let error = problem.min_bins(Rectangle::new(0, 11)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cannot fit rectangles into an empty 0x11 bin"
+        );
+    }
+
+    #[test]
+    fn strict_count() {
+        assert!(Problem::from_str_strict(input).is_ok());
+
+        let rectangles: String = (1..=8).map(|i| format!("\n{} {}", i, i)).collect();
+        let s = format!(
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 10{}",
+            rectangles
+        );
+        assert!(s.parse::<Problem>().is_ok());
+
+        let error = Problem::from_str_strict(&s).unwrap_err().to_string();
+        assert!(error.contains("Expected 10 rectangles, but parsed 8"));
+        assert!(error.contains("1 1, 2 2, 3 3, ..., 6 6, 7 7, 8 8"));
+    }
+
+    #[test]
+    fn edit_rectangles() {
+        let mut problem: Problem = input.parse().unwrap();
+        problem.source = Some(Rectangle::new(22, 9));
+
+        assert_eq!(problem.remove_rectangle(2), None);
+        assert_eq!(problem.source, Some(Rectangle::new(22, 9)));
+
+        assert_eq!(problem.remove_rectangle(0), Some(Rectangle::new(12, 8)));
+        assert_eq!(problem.rectangles(), &[Rectangle::new(10, 9)]);
+        assert_eq!(problem.source, None);
+
+        problem.source = Some(Rectangle::new(10, 9));
+        problem.add_rectangle(Rectangle::new(3, 4));
+        assert_eq!(problem.rectangle_count(), 2);
+        assert_eq!(problem.source, None);
+    }
+
+    #[test]
+    fn preprocessing() {
+        let mut problem: Problem = input.parse().unwrap();
+        problem.rectangles_mut()[0] = Rectangle::new(2, 2);
+        problem.sort_by_area();
+        assert_eq!(
+            problem.rectangles(),
+            &[Rectangle::new(10, 9), Rectangle::new(2, 2)]
+        );
+
+        problem.source = Some(Rectangle::new(12, 9));
+        problem.scale(3).unwrap();
+        assert_eq!(
+            problem.rectangles(),
+            &[Rectangle::new(30, 27), Rectangle::new(6, 6)]
+        );
+        assert_eq!(problem.variant, Variant::Fixed(66));
+        assert_eq!(problem.source, Some(Rectangle::new(36, 27)));
+
+        // The rectangles still fit, but the fixed height does not
+        let before = problem.clone();
+        assert!(problem.scale(u32::max_value() / 30).is_err());
+        assert_eq!(problem, before);
+
+        let mut shuffled = problem.clone();
+        shuffled.shuffle(&mut rand::thread_rng());
+        shuffled.sort_by_area();
+        assert_eq!(shuffled, problem);
+    }
+
+    #[test]
+    fn with_rotation() {
+        let mut problem: Problem = input.parse().unwrap();
+        problem.source = Some(Rectangle::new(22, 9));
+        let rotated = problem.with_rotation(true);
+
+        assert!(rotated.allow_rotation);
+        assert_ne!(rotated, problem);
+        assert_eq!(rotated.with_rotation(false), problem);
+    }
+
+    #[test]
+    fn perturb() {
+        let mut rng = rand::thread_rng();
+        let problem: Problem = input.parse().unwrap();
+        let perturbed = problem.perturb(&mut rng, 3);
+        assert_ne!(perturbed.rectangles, problem.rectangles);
+
+        assert_eq!(perturbed.rectangle_count(), problem.rectangle_count());
+        assert_eq!(perturbed.allow_rotation, problem.allow_rotation);
+        assert_eq!(perturbed.source, None);
+        assert!(perturbed.feasible().is_ok());
+        for (r, p) in problem.rectangles.iter().zip(perturbed.rectangles()) {
+            assert!((i64::from(r.width) - i64::from(p.width)).abs() <= 3);
+            assert!((i64::from(r.height) - i64::from(p.height)).abs() <= 3);
+        }
+
+        let square = "container height: fixed 5\nrotations allowed: yes\nnumber of rectangles: \
+                      1\n5 5";
+        let problem: Problem = square.parse().unwrap();
+        for _ in 0..50 {
+            let perturbed = problem.perturb(&mut rng, 3);
+            let r = perturbed.rectangles()[0];
+            assert!(r.width.min(r.height) <= 5);
+            assert!(perturbed.feasible().is_ok());
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let p: Problem = input.parse().unwrap();
+        let merged = Problem::merge(&[p.clone(), p.clone()]).unwrap();
+
+        assert_eq!(merged.variant, p.variant);
+        assert_eq!(merged.rectangle_count(), 4);
+        assert_eq!(&merged.rectangles()[2..], p.rectangles());
+
+        let mut rotated = p.clone();
+        rotated.allow_rotation = true;
+        assert!(Problem::merge(&[p, rotated]).is_err());
+        assert!(Problem::merge(&[]).is_err());
+    }
+
+    #[test]
+    fn variant_accessors() {
+        let mut problem: Problem = input.parse().unwrap();
+        assert_eq!(problem.fixed_height(), Some(22));
+        assert!(problem.is_fixed());
+        assert!(!problem.is_free());
+
+        problem.variant = Variant::Free;
+        assert_eq!(problem.fixed_height(), None);
+        assert!(!problem.is_fixed());
+        assert!(problem.is_free());
     }
 
     #[test]
@@ -331,6 +1348,80 @@ mod tests {
         assert_eq!(input, format!("{}", input.parse::<Problem>().unwrap()))
     }
 
+    #[test]
+    fn comments_are_skipped() {
+        let commented = "# source: 22 22\ncontainer height: fixed 22\nrotations allowed: \
+                         no\n# a comment\nnumber of rectangles: 2\n12 8\n10 9";
+
+        let expected: Problem = input.parse().unwrap();
+        let result: Problem = commented.parse().unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn named_round_trip() {
+        let named = format!("name: two rectangles\n{}", input);
+        let result: Problem = named.parse().unwrap();
+
+        assert_eq!(result.name, Some("two rectangles".to_string()));
+        assert!(result.same_instance(&input.parse().unwrap()));
+        assert_eq!(named, result.to_string());
+    }
+
+    #[test]
+    fn greedy_bottom_left() {
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(20, 20));
+        generator.rectangles(30);
+        generator.variant(Variant::Fixed(20));
+
+        for _ in 0..10 {
+            let problem = generator.generate();
+            let solution = problem.greedy_bottom_left().unwrap();
+            assert!(solution.is_valid());
+            assert_eq!(solution.container().unwrap().height, 20);
+        }
+
+        let mut problem: Problem = input.parse().unwrap();
+        problem.variant = Variant::Free;
+        assert!(problem.greedy_bottom_left().unwrap().is_valid());
+
+        problem.variant = Variant::Fixed(8);
+        assert!(problem.greedy_bottom_left().is_err());
+
+        // The skyline does not grow with the height of the strip
+        let tall = "container height: fixed 1000000000\nrotations allowed: no\nnumber of \
+                    rectangles: 3\n10 600000000\n10 500000000\n5 400000000";
+        let solution = tall
+            .parse::<Problem>()
+            .unwrap()
+            .greedy_bottom_left()
+            .unwrap();
+        assert!(solution.is_valid());
+        assert_eq!(
+            solution.container().unwrap(),
+            Rectangle::new(20, 1_000_000_000)
+        );
+    }
+
+    #[test]
+    fn skyline() {
+        let mut skyline = Skyline::new(10);
+        assert_eq!(skyline.leftmost_position(10), Some(Point::new(0, 0)));
+        assert_eq!(skyline.leftmost_position(11), None);
+
+        skyline.raise(0, 4, 5);
+        skyline.raise(6, 4, 3);
+        assert_eq!(skyline.steps, vec![(0, 5), (4, 0), (6, 3)]);
+        assert_eq!(skyline.leftmost_position(2), Some(Point::new(0, 4)));
+        assert_eq!(skyline.leftmost_position(3), Some(Point::new(3, 4)));
+        assert_eq!(skyline.leftmost_position(7), Some(Point::new(5, 0)));
+
+        skyline.raise(4, 2, 3);
+        assert_eq!(skyline.steps, vec![(0, 5), (4, 3), (6, 3)]);
+        assert_eq!(skyline.leftmost_position(6), Some(Point::new(3, 4)));
+    }
+
     #[test]
     fn generate_from() {
         let r = Rectangle::new(1000, 1000);
@@ -339,4 +1430,206 @@ mod tests {
 
         assert_eq!(a, 1000 * 1000);
     }
+
+    #[test]
+    fn no_source_piece() {
+        let source = Rectangle::new(4, 4);
+        for &n in &[2, 3] {
+            let mut generator = Generator::new();
+            generator.container(source);
+            generator.rectangles(n);
+            generator.shuffle(true);
+            let p = generator.generate();
+            assert_eq!(p.rectangle_count(), n);
+            assert!(!p.rectangles.contains(&source));
+
+            // No cut leaves two pieces of area 10, so only the first cut is made
+            generator.min_area(10);
+            let p = generator.generate();
+            assert_eq!(p.rectangle_count(), 2);
+            assert!(!p.rectangles.contains(&source));
+            assert_eq!(p.rectangles.iter().map(Rectangle::area).sum::<u64>(), 16);
+        }
+    }
+
+    #[test]
+    fn from_sample() {
+        let sample: Vec<Rectangle> = (1..=4).map(|i| Rectangle::new(10 * i, 5 * i)).collect();
+        let mut generator = Generator::from_sample(&sample);
+        generator.rectangles(2000);
+        generator.allow_rotation(false);
+        generator.variant(Variant::Fixed(1));
+        let p = generator.generate();
+
+        assert_eq!(p.rectangle_count(), 2000);
+        assert_eq!(p.source, None);
+        let max_height = p.rectangles.iter().map(|r| r.height).max().unwrap();
+        assert_eq!(p.variant, Variant::Fixed(max_height));
+
+        let mean = |f: fn(&Rectangle) -> u32| {
+            p.rectangles.iter().map(|r| f64::from(f(r))).sum::<f64>() / 2000.
+        };
+        assert!((mean(|r| r.width) - 25.).abs() < 2.5);
+        assert!((mean(|r| r.height) - 12.5).abs() < 1.25);
+    }
+
+    #[test]
+    fn generator_defaults() {
+        let p = Generator::new().generate();
+        let source = p.source.unwrap();
+        let area: u64 = p.rectangles().iter().map(Rectangle::area).sum();
+
+        assert!(N_DEFAULTS.contains(&p.rectangle_count()));
+        assert_eq!(area, source.area());
+        assert!(p.feasible().is_ok());
+    }
+
+    #[test]
+    fn container_ratio() {
+        let mut generator = Generator::new();
+        generator.container_ratio(16, 9);
+
+        for &n in &[3, 25, 5000] {
+            generator.rectangles(n);
+            let source = generator.generate().source.unwrap();
+            let ratio = source.width as f32 / source.height as f32;
+            assert!((ratio - 16. / 9.).abs() < 0.05, "{} for n = {}", ratio, n);
+        }
+    }
+
+    #[test]
+    fn container_aspect() {
+        let mut generator = Generator::new();
+        generator.rectangles(25);
+
+        for &target in &[2., 0.5, 0.08] {
+            generator.container_aspect(target);
+            let source = generator.generate().source.unwrap();
+            let aspect = source.width as f32 / source.height as f32;
+            assert!(
+                (aspect / target - 1.).abs() < 0.1,
+                "{} for {}",
+                aspect,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn max_aspect_ratio() {
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(64, 64));
+        generator.rectangles(40);
+        generator.max_aspect_ratio(2.);
+
+        for _ in 0..20 {
+            let p = generator.generate();
+            let a: u64 = p.rectangles.iter().map(Rectangle::area).sum();
+            assert_eq!(a, 64 * 64);
+            assert!(p.rectangles.iter().all(|r| r.aspect_ratio() <= 2.));
+        }
+    }
+
+    #[test]
+    fn min_area() {
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(100, 100));
+        generator.rectangles(1000);
+        generator.min_area(30);
+
+        for _ in 0..20 {
+            let p = generator.generate();
+            let a: u64 = p.rectangles.iter().map(Rectangle::area).sum();
+            assert_eq!(a, 100 * 100);
+            assert!(p.rectangles.iter().all(|r| r.area() >= 30));
+        }
+
+        generator.max_aspect_ratio(2.);
+        let p = generator.generate();
+        assert!(p
+            .rectangles
+            .iter()
+            .all(|r| r.area() >= 30 && r.aspect_ratio() <= 2.));
+    }
+
+    #[test]
+    fn min_distinct_sizes() {
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(60, 60));
+        generator.rectangles(40);
+        generator.min_distinct_sizes(25);
+
+        for _ in 0..10 {
+            assert!(generator.try_generate().unwrap().distinct_sizes() >= 25);
+        }
+
+        generator.container(Rectangle::new(2, 2));
+        generator.rectangles(4);
+        generator.min_distinct_sizes(2);
+        assert_eq!(generator.generate().distinct_sizes(), 1);
+
+        let error = generator.try_generate().unwrap_err().to_string();
+        assert!(error.contains("at least 2 distinct sizes"));
+    }
+
+    #[test]
+    fn default_counts() {
+        let mut generator = Generator::new();
+        generator.default_counts(&[4, 7]);
+
+        for _ in 0..20 {
+            let n = generator.generate().rectangles.len();
+            assert!(n == 4 || n == 7);
+        }
+    }
+
+    #[test]
+    fn from_path_limited() {
+        let path = env::temp_dir().join("packt_from_path_limited.txt");
+        File::create(&path)
+            .and_then(|mut f| f.write_all(input.as_bytes()))
+            .unwrap();
+
+        let limited = Problem::from_path_limited(&path, 1).unwrap();
+        assert!(limited.truncated);
+        assert_eq!(limited.rectangles, vec![Rectangle::new(12, 8)]);
+
+        let full = Problem::from_path_limited(&path, 2).unwrap();
+        assert!(!full.truncated);
+        assert_eq!(full, input.parse().unwrap());
+    }
+
+    #[test]
+    fn to_writer() {
+        let problem: Problem = input.parse().unwrap();
+        let mut buffer = Vec::new();
+        problem.to_writer(&mut buffer).unwrap();
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), problem.to_string());
+    }
+
+    #[test]
+    fn size_palette() {
+        let palette = vec![Rectangle::new(2, 3), Rectangle::new(4, 1)];
+        let mut generator = Generator::new();
+        generator.container(Rectangle::new(30, 30));
+        generator.rectangles(50);
+        generator.size_palette(palette.clone());
+
+        for _ in 0..10 {
+            let p = generator.generate();
+            assert!(!p.rectangles.is_empty());
+            assert!(p.rectangles.iter().all(|r| palette.contains(r)));
+            assert_eq!(p.palette.as_ref(), Some(&palette));
+        }
+    }
+
+    #[test]
+    fn total_area_checksum() {
+        let matching: Problem = format!("{}\ntotal area: 186", input).parse().unwrap();
+        assert_eq!(matching, input.parse().unwrap());
+
+        let mismatch = format!("{}\ntotal area: 96", input).parse::<Problem>();
+        assert!(mismatch.is_err());
+    }
 }
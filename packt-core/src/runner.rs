@@ -1,9 +1,13 @@
 use failure::Error;
 use problem::Problem;
-use solution::{Evaluation, Solution};
+use solution::{Evaluation, Phases, Solution};
+use solver::{self, Solver};
 use std::{
+    collections::HashSet,
+    ffi::{OsStr, OsString},
     path::PathBuf,
     process::{Command, Stdio},
+    sync::Mutex,
     time::{Duration, Instant},
 };
 use tokio::prelude::*;
@@ -11,43 +15,853 @@ use tokio_core::reactor::Handle;
 use tokio_io;
 use tokio_process::CommandExt;
 
+lazy_static! {
+    /// The process ids of the solvers that are currently running, see `kill_live_children`.
+    static ref LIVE_CHILDREN: Mutex<HashSet<u32>> = Mutex::new(HashSet::new());
+
+    /// Whether `taskset` can be run to pin solvers to a core, see `SolverCommand::pin_to`.
+    /// Checked once, warning if it cannot.
+    static ref TASKSET_AVAILABLE: bool = {
+        let available = Command::new("taskset")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_or(false, |status| status.success());
+        if !available {
+            warn!("taskset was not found, so solvers are not pinned to a core");
+        }
+
+        available
+    };
+}
+
+/// Keeps a solver in `LIVE_CHILDREN` until it is dropped, which happens once the solver has
+/// finished or its future is dropped.
+struct LiveChild(u32);
+
+impl LiveChild {
+    fn register(pid: u32) -> LiveChild {
+        LIVE_CHILDREN.lock().unwrap().insert(pid);
+        LiveChild(pid)
+    }
+}
+
+impl Drop for LiveChild {
+    fn drop(&mut self) {
+        if let Ok(mut children) = LIVE_CHILDREN.lock() {
+            children.remove(&self.0);
+        }
+    }
+}
+
+/// Kills every solver that is currently running, so none are left behind when the program
+/// is interrupted. The futures of the killed solvers fail once the solvers have exited.
+///
+/// Uses `kill` on Unix and `taskkill` on Windows rather than the child handles, which are
+/// owned by the event loop, so this may be called from any thread, e.g. a signal handler.
+pub fn kill_live_children() {
+    let children = match LIVE_CHILDREN.lock() {
+        Ok(children) => children.clone(),
+        Err(_) => return,
+    };
+
+    for pid in children {
+        info!("Killing solver with pid {}", pid);
+        let pid = pid.to_string();
+        let status = if cfg!(windows) {
+            Command::new("taskkill")
+                .args(&["/F", "/PID", &pid])
+                .status()
+        } else {
+            Command::new("kill").arg(&pid).status()
+        };
+
+        if let Err(e) = status {
+            warn!("Failed to kill solver with pid {}: {}", pid, e);
+        }
+    }
+}
+
+/// Runs `solver` on `problem` and evaluates its solution.
+///
+/// Problems that are provably infeasible fail right away, without spawning the solver.
 pub fn solve_async(
     solver: &PathBuf,
     problem: Problem,
     handle: Handle,
     delta: Duration,
 ) -> impl Future<Item = Evaluation, Error = Error> {
-    let mut command = Command::new("java");
+    solve_async_pinned(solver, problem, handle, delta, None)
+}
+
+/// Like `solve_async`, but passes `jvm_args` to the JVM, before `-jar`, and `program_args`
+/// to the solver itself, after the jar-file. Solvers that are not jar-files only get the
+/// program arguments. See `SolverCommand` for more options.
+pub fn solve_async_with_args(
+    solver: &PathBuf,
+    jvm_args: &[String],
+    program_args: &[String],
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    let command = command_with_args(solver, jvm_args, program_args).build();
+    solve_command_async(command, problem, handle, delta)
+}
+
+/// A `SolverCommand` for `solver` with `jvm_args` and `program_args`, as used by
+/// `solve_async_with_args`, for further configuration.
+pub fn command_with_args(
+    solver: &PathBuf,
+    jvm_args: &[String],
+    program_args: &[String],
+) -> SolverCommand {
+    let mut command = SolverCommand::new(solver.clone());
+    for arg in jvm_args {
+        command = command.jvm_arg(arg.clone());
+    }
+    for arg in program_args {
+        command = command.solver_arg(arg.clone());
+    }
+
     command
-        .arg("-jar")
-        .arg(solver)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped());
+}
+
+/// Like `solve_async`, but the deadline counts from the moment this function is called,
+/// rather than from when the solver has been spawned and its stdin opened.
+///
+/// `solve_async` gives the solver the full `delta` once it is running, however long it
+/// took to get there. Here, spawning the process and any delay before the returned future
+/// is first polled are part of the budget, so a solver that is slow to start times out
+/// earlier. The duration in the evaluation is measured the same way in both cases.
+pub fn solve_async_total_deadline(
+    solver: &PathBuf,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    let deadline = Instant::now() + delta;
+    run_with_deadline(
+        solver_command(solver, None),
+        problem,
+        handle,
+        delta,
+        true,
+        Some(deadline),
+    ).and_then(evaluate)
+}
+
+/// Like `solve_async`, but solves `problem` in-process with `solver` instead of spawning an
+/// external solver.
+pub fn solve_in_process<S: Solver>(
+    solver: S,
+    problem: Problem,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    future::lazy(move || solver::solve_and_evaluate(&solver, problem))
+}
+
+/// Like `solve_async`, but skips the feasibility check, so the solver is always spawned.
+/// Useful to test how a solver behaves on infeasible input.
+pub fn solve_async_unchecked(
+    solver: &PathBuf,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    run_with(solver_command(solver, None), problem, handle, delta, false).and_then(evaluate)
+}
+
+/// Like `solve_async`, but pins the solver process to CPU `core` if one is given.
+///
+/// Pinning is done by launching the solver through `taskset`, so it only works on Linux
+/// with util-linux installed. On other platforms `core` is ignored.
+pub fn solve_async_pinned(
+    solver: &PathBuf,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+    core: Option<usize>,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    run_async(solver, problem, handle, delta, core).and_then(evaluate)
+}
+
+/// Evaluates the result of `run_async`, attaching the phase timings to the evaluation.
+pub fn evaluate(
+    (mut solution, duration, phases): (Solution, Duration, Phases),
+) -> Result<Evaluation, Error> {
+    let mut evaluation = solution.evaluate(duration)?;
+    evaluation.phases = Some(phases);
+    Ok(evaluation)
+}
+
+/// Runs the solver on `problem` and parses its output, without evaluating the solution.
+///
+/// Resolves to the solution, the time the solver took and the time spent in every phase.
+pub fn run_async(
+    solver: &PathBuf,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+    core: Option<usize>,
+) -> impl Future<Item = (Solution, Duration, Phases), Error = Error> {
+    run_with(solver_command(solver, core), problem, handle, delta, true)
+}
+
+/// Like `run_async`, but runs `command`, which is typically built with `SolverCommand`.
+pub fn run_command_async(
+    command: Command,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+) -> impl Future<Item = (Solution, Duration, Phases), Error = Error> {
+    run_with(command, problem, handle, delta, true)
+}
+
+fn run_with(
+    command: Command,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+    check_feasible: bool,
+) -> impl Future<Item = (Solution, Duration, Phases), Error = Error> {
+    run_with_deadline(command, problem, handle, delta, check_feasible, None)
+}
+
+/// Like `run_with`, but times out at `deadline` if one is given, instead of `delta` after the
+/// solver was spawned.
+fn run_with_deadline(
+    command: Command,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+    check_feasible: bool,
+    deadline: Option<Instant>,
+) -> impl Future<Item = (Solution, Duration, Phases), Error = Error> {
+    let feasible = if check_feasible {
+        problem.feasible()
+    } else {
+        Ok(())
+    };
 
     let input = problem.to_string();
+    future::result(feasible).and_then(move |()| {
+        run_feasible(command, input, problem, handle, delta, deadline)
+    })
+}
+
+fn run_feasible(
+    mut command: Command,
+    input: String,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+    deadline: Option<Instant>,
+) -> impl Future<Item = (Solution, Duration, Phases), Error = Error> {
     future::lazy(move || {
-        let mut child = command
+        let spawned = Instant::now();
+        command
             .spawn_async(&handle)
-            .expect("Failed to spawn child process");
+            .map(|child| (child, spawned))
+            .map_err(|e| format_err!("Failed to spawn solver: {}", e))
+    }).and_then(move |(mut child, spawned)| {
+        let stdin = child.stdin().take().expect("Failed to open stdin");
+        let start = Instant::now();
+        let spawn = start.duration_since(spawned);
+        let live = LiveChild::register(child.id());
+        info!("Spawned solver with pid {}", child.id());
+        debug!("Spawned solver in {:?}", spawn);
+
+        tokio_io::io::write_all(stdin, input)
+            .map(move |_| (child, Instant::now()))
+            .and_then(|(child, written)| child.wait_with_output().map(move |c| (c, written)))
+            .map(move |(output, written)| {
+                drop(live);
+                let end = Instant::now();
+                let (write, wait) = (written.duration_since(start), end.duration_since(written));
+                info!("Solver finished after {:?}", end.duration_since(start));
+                debug!("Wrote problem in {:?}, solver finished after {:?}", write, wait);
+                (output, end.duration_since(start), (spawn, write, wait))
+            })
+            .deadline(deadline.unwrap_or(start + delta))
+            .map_err(move |e| {
+                if e.is_elapsed() {
+                    warn!("Solver timed out after {:?}", delta);
+                }
+                e
+            })
+            .from_err()
+    }).and_then(|(output, duration, (spawn, write, wait))| {
+        let parsing = Instant::now();
+        let output = String::from_utf8_lossy(&output.stdout);
+        parse_output(&problem, &output)
+            .map_err(|e| {
+                error!("Failed to parse solver output: {}", e);
+                e
+            })
+            .map(|mut solution| {
+                solution.source(problem);
+                let parse = parsing.elapsed();
+                debug!("Parsed solution in {:?}", parse);
 
+                let phases = Phases {
+                    spawn,
+                    write,
+                    wait,
+                    parse,
+                };
+                (solution, duration, phases)
+            })
+    })
+}
+
+/// The line separating consecutive problems, and consecutive solutions, in
+/// `solve_many_async`.
+pub const DELIMITER: &str = "---";
+
+/// Solves all `problems` with a single solver process, so the JVM only starts once.
+///
+/// The problems are written to the solver's stdin separated by `DELIMITER` lines, and the
+/// solver is expected to print its solutions in the same order, separated the same way. It
+/// gets `delta_each` per problem in total. Since only the combined running time can be
+/// measured, every evaluation reports the average duration.
+pub fn solve_many_async(
+    solver: &PathBuf,
+    problems: Vec<Problem>,
+    handle: Handle,
+    delta_each: Duration,
+) -> impl Future<Item = Vec<Result<Evaluation, Error>>, Error = Error> {
+    solve_many_with(solver_command(solver, None), problems, handle, delta_each)
+}
+
+fn solve_many_with(
+    mut command: Command,
+    problems: Vec<Problem>,
+    handle: Handle,
+    delta_each: Duration,
+) -> impl Future<Item = Vec<Result<Evaluation, Error>>, Error = Error> {
+    let n = problems.len() as u32;
+    let budget = match delta_each.checked_mul(n) {
+        // Without problems there is nothing to wait for, and the deadline would pass at once
+        _ if n == 0 => return future::Either::A(future::ok(Vec::new())),
+        Some(budget) => budget,
+        None => {
+            let error = format_err!("The time budget for {} problems is too large", n);
+            return future::Either::A(future::err(error));
+        }
+    };
+    let input = problems
+        .iter()
+        .map(Problem::to_string)
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}\n", DELIMITER));
+
+    let solve = future::lazy(move || {
+        command
+            .spawn_async(&handle)
+            .map_err(|e| format_err!("Failed to spawn solver: {}", e))
+    }).and_then(move |mut child| {
         let stdin = child.stdin().take().expect("Failed to open stdin");
         let start = Instant::now();
+        let live = LiveChild::register(child.id());
+        info!("Spawned solver with pid {} for {} problems", child.id(), n);
 
         tokio_io::io::write_all(stdin, input)
             .map(move |_| (child, start))
             .and_then(|(child, start)| child.wait_with_output().map(move |c| (c, start)))
-            .map(|(output, start)| {
+            .map(move |(output, start)| {
+                drop(live);
                 let duration = Instant::now().duration_since(start);
+                info!("Solver finished after {:?}", duration);
                 (output, duration)
             })
-            .deadline(start + delta)
-    }).from_err()
-        .and_then(|(output, duration)| {
-            let output = String::from_utf8_lossy(&output.stdout);
-            output.parse::<Solution>().map(|mut solution| {
+            .deadline(start + budget)
+            .map_err(move |e| {
+                if e.is_elapsed() {
+                    warn!("Solver timed out after {:?}", budget);
+                }
+                e
+            })
+            .from_err()
+    }).map(move |(output, duration)| {
+        let output = String::from_utf8_lossy(&output.stdout);
+        let duration = duration / n.max(1);
+        let mut outputs = split_delimited(&output).into_iter();
+
+        problems
+            .into_iter()
+            .map(|problem| {
+                let output = outputs
+                    .next()
+                    .ok_or_else(|| format_err!("Solver did not output a solution"))?;
+                let mut solution = parse_output(&problem, &output).map_err(|e| {
+                    error!("Failed to parse solver output: {}", e);
+                    e
+                })?;
                 solution.source(problem);
-                (solution, duration)
+                solution.evaluate(duration)
             })
-        })
-        .and_then(move |(mut solution, duration)| solution.evaluate(duration))
+            .collect()
+    });
+
+    future::Either::B(solve)
+}
+
+/// Parses solver output for `problem`, which is either JSON or the usual text format. Text
+/// output may start with diagnostics, see `Solution::extract_from`.
+///
+/// If the solver printed its running time on a `time: N.NNN` line, it is recorded on the
+/// solution, see `Solution::report_duration`.
+fn parse_output(problem: &Problem, output: &str) -> Result<Solution, Error> {
+    let (output, reported) = extract_reported_time(output);
+    let mut solution = if output.trim_start().starts_with('{') {
+        Solution::from_json(problem, &output)?
+    } else {
+        Solution::extract_from(&output)?
+    };
+
+    if let Some(duration) = reported {
+        solution.report_duration(duration);
+    }
+    Ok(solution)
+}
+
+/// Removes all `time:` lines from `output`, returning the rest of the output and the last
+/// time in seconds that could be parsed.
+fn extract_reported_time(output: &str) -> (String, Option<Duration>) {
+    let mut reported = None;
+    let mut rest = String::with_capacity(output.len());
+    for line in output.lines() {
+        if line.trim().starts_with("time:") {
+            let secs = line.trim()["time:".len()..].trim().parse::<f64>();
+            match secs {
+                Ok(secs) if secs >= 0. => {
+                    let nanos = (secs.fract() * 1e9) as u32;
+                    reported = Some(Duration::new(secs.trunc() as u64, nanos));
+                }
+                _ => warn!("Ignoring unparsable reported time: {}", line),
+            }
+        } else {
+            rest.push_str(line);
+            rest.push('\n');
+        }
+    }
+
+    (rest, reported)
+}
+
+fn split_delimited(s: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    for line in s.lines() {
+        if line.trim() == DELIMITER {
+            parts.push(String::new());
+        } else {
+            let part = parts.last_mut().unwrap();
+            part.push_str(line);
+            part.push('\n');
+        }
+    }
+
+    parts
+}
+
+fn solver_command(solver: &PathBuf, core: Option<usize>) -> Command {
+    let command = SolverCommand::new(solver.clone());
+    match core {
+        Some(core) => command.pin_to(core).build(),
+        None => command.build(),
+    }
+}
+
+/// Solves `problem` by running `command`, which is typically built with `SolverCommand`.
+pub fn solve_command_async(
+    command: Command,
+    problem: Problem,
+    handle: Handle,
+    delta: Duration,
+) -> impl Future<Item = Evaluation, Error = Error> {
+    run_with(command, problem, handle, delta, true).and_then(evaluate)
+}
+
+/// Builds the command used to launch a solver.
+///
+/// Solvers ending in `.jar` are started with `java -jar`, preceded by the JVM arguments.
+/// Any other solver is treated as an executable and started directly, ignoring the JVM
+/// arguments. Solver arguments are always passed after the solver itself.
+///
+/// ```
+/// # use packt_core::runner::SolverCommand;
+/// let command = SolverCommand::new("solver.jar")
+///     .jvm_arg("-Xmx4g")
+///     .solver_arg("--algo=bl")
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SolverCommand {
+    solver: PathBuf,
+    jvm_args: Vec<OsString>,
+    solver_args: Vec<OsString>,
+    core: Option<usize>,
+    max_memory: Option<u64>,
+}
+
+impl SolverCommand {
+    pub fn new<P: Into<PathBuf>>(solver: P) -> Self {
+        SolverCommand {
+            solver: solver.into(),
+            jvm_args: Vec::new(),
+            solver_args: Vec::new(),
+            core: None,
+            max_memory: None,
+        }
+    }
+
+    pub fn jvm_arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.jvm_args.push(arg.into());
+        self
+    }
+
+    pub fn solver_arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.solver_args.push(arg.into());
+        self
+    }
+
+    /// Pins the solver to CPU `core` by launching it through `taskset`.
+    ///
+    /// This only works on Linux with util-linux installed; elsewhere it is ignored. If
+    /// `taskset` cannot be found, a warning is logged and the solver runs unpinned.
+    pub fn pin_to(mut self, core: usize) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Limits the address space of the solver to `bytes`, so that a solver running out of
+    /// control fails rather than exhausting the memory of the machine. The solver then
+    /// typically aborts with an allocation failure, which is reported as a failed run.
+    ///
+    /// This only works on Unix, where it sets `RLIMIT_AS` in the child before the solver is
+    /// started; elsewhere it is ignored. The JVM reserves address space well beyond its heap,
+    /// so for jar-files the limit should leave ample room above `-Xmx`.
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    fn is_jar(&self) -> bool {
+        self.solver.extension().map_or(false, |ext| ext == "jar")
+    }
+
+    /// Whether `build` launches the solver through `taskset`, see `pin_to`.
+    fn is_pinned(&self) -> bool {
+        self.core.is_some() && cfg!(target_os = "linux") && *TASKSET_AVAILABLE
+    }
+
+    fn solver_program(&self) -> &OsStr {
+        if self.is_jar() {
+            "java".as_ref()
+        } else {
+            self.solver.as_os_str()
+        }
+    }
+
+    /// The program that `build` runs: `taskset` when pinned, `java` for jar-files and the
+    /// solver itself otherwise.
+    pub fn program(&self) -> &OsStr {
+        if self.is_pinned() {
+            "taskset".as_ref()
+        } else {
+            self.solver_program()
+        }
+    }
+
+    /// The arguments that `build` passes to `program`.
+    pub fn args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let (true, Some(core)) = (self.is_pinned(), self.core) {
+            args.push("-c".into());
+            args.push(core.to_string().into());
+            args.push(self.solver_program().to_os_string());
+        }
+
+        if self.is_jar() {
+            args.extend(self.jvm_args.iter().cloned());
+            args.push("-jar".into());
+            args.push(self.solver.clone().into_os_string());
+        }
+
+        args.extend(self.solver_args.iter().cloned());
+        args
+    }
+
+    pub fn build(&self) -> Command {
+        let mut command = Command::new(self.program());
+        command
+            .args(self.args())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+        if let Some(bytes) = self.max_memory {
+            limit_memory(&mut command, bytes);
+        }
+
+        command
+    }
+}
+
+#[cfg(unix)]
+fn limit_memory(command: &mut Command, bytes: u64) {
+    use std::{io, os::unix::process::CommandExt as UnixCommandExt};
+
+    let limit = libc::rlimit {
+        rlim_cur: bytes as libc::rlim_t,
+        rlim_max: bytes as libc::rlim_t,
+    };
+    // Only calls `setrlimit`, which is async-signal-safe, between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_memory(_command: &mut Command, _bytes: u64) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use geometry::Rectangle;
+    use std::thread;
+    use tokio_core::reactor::Core;
+
+    /// Echoes every problem followed by a single placement at the origin.
+    const FAKE_SOLVER: &str = "/^---$/ { print \"placement of rectangles\"; print \"0 0\" } \
+                               { print } \
+                               END { print \"placement of rectangles\"; print \"0 0\" }";
+
+    #[test]
+    fn solve_many() {
+        let mut command = Command::new("awk");
+        command
+            .arg(FAKE_SOLVER)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+
+        let problems: Vec<Problem> = ["free", "fixed 3"]
+            .iter()
+            .map(|v| {
+                format!(
+                    "container height: {}\nrotations allowed: no\nnumber of rectangles: 1\n2 3",
+                    v
+                ).parse()
+                    .unwrap()
+            })
+            .collect();
+
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let future = solve_many_with(command, problems, handle, Duration::from_secs(5));
+        let results = core.run(future).unwrap();
+
+        assert_eq!(results.len(), 2);
+        for result in results {
+            assert_eq!(result.unwrap().container, Rectangle::new(2, 3));
+        }
+    }
+
+    #[test]
+    fn solve_many_edge_cases() {
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+        let mut core = Core::new().unwrap();
+
+        // Neither spawns the solver, which does not exist
+        let command = Command::new("/nonexistent/solver");
+        let future = solve_many_with(command, Vec::new(), core.handle(), Duration::from_secs(5));
+        assert!(core.run(future).unwrap().is_empty());
+
+        let command = Command::new("/nonexistent/solver");
+        let problems = vec![problem.clone(), problem];
+        let delta = Duration::from_secs(u64::max_value());
+        let future = solve_many_with(command, problems, core.handle(), delta);
+        let error = core.run(future).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The time budget for 2 problems is too large"
+        );
+    }
+
+    #[test]
+    fn phases() {
+        let mut command = Command::new("awk");
+        command
+            .arg(FAKE_SOLVER)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let start = Instant::now();
+        let future =
+            run_with(command, problem, handle, Duration::from_secs(5), true).and_then(evaluate);
+        let evaluation = core.run(future).unwrap();
+        let total = start.elapsed();
+
+        let phases = evaluation.phases.unwrap();
+        assert_eq!(phases.write + phases.wait, evaluation.duration);
+        assert!(phases.spawn + evaluation.duration + phases.parse <= total);
+    }
+
+    #[test]
+    fn reported_time() {
+        let solver = "{ print } END { print \"placement of rectangles\"; print \"0 0\"; \
+                      print \"time: 1.250\" }";
+        let mut command = Command::new("awk");
+        command
+            .arg(solver)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped());
+
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let future =
+            run_with(command, problem, handle, Duration::from_secs(5), true).and_then(evaluate);
+        let evaluation = core.run(future).unwrap();
+        assert_eq!(
+            evaluation.solver_reported_duration,
+            Some(Duration::from_millis(1250))
+        );
+
+        let (_, reported) = extract_reported_time("0 0\n");
+        assert_eq!(reported, None);
+    }
+
+    #[test]
+    fn total_deadline() {
+        let command = || {
+            let mut command = Command::new("awk");
+            command
+                .arg(FAKE_SOLVER)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped());
+            command
+        };
+
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+
+        let mut core = Core::new().unwrap();
+        let delta = Duration::from_millis(500);
+        let after_spawn = run_with(command(), problem.clone(), core.handle(), delta, true);
+        let total = run_with_deadline(
+            command(),
+            problem,
+            core.handle(),
+            delta,
+            true,
+            Some(Instant::now() + delta),
+        );
+
+        // Stands in for a solver that takes longer than `delta` to start
+        thread::sleep(Duration::from_millis(600));
+        assert!(core.run(after_spawn).is_ok());
+        assert!(core.run(total).is_err());
+    }
+
+    #[test]
+    fn live_children() {
+        let pid = u32::max_value();
+        let live = LiveChild::register(pid);
+        assert!(LIVE_CHILDREN.lock().unwrap().contains(&pid));
+
+        drop(live);
+        assert!(!LIVE_CHILDREN.lock().unwrap().contains(&pid));
+    }
+
+    #[test]
+    fn infeasible() {
+        let command = Command::new("/nonexistent/solver");
+        let problem: Problem =
+            "container height: fixed 2\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+
+        // Spawning the nonexistent solver would fail differently, so this checks it is skipped
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let future = run_with(command, problem, handle, Duration::from_secs(5), true);
+        let error = core.run(future).unwrap_err().to_string();
+        assert!(error.contains("does not fit within the fixed height 2"));
+    }
+
+    #[test]
+    fn spawn_failure() {
+        let command = Command::new("/nonexistent/solver");
+        let problem: Problem =
+            "container height: free\nrotations allowed: no\nnumber of rectangles: 1\n2 3"
+                .parse()
+                .unwrap();
+
+        let mut core = Core::new().unwrap();
+        let handle = core.handle();
+        let future = run_with(command, problem, handle, Duration::from_secs(5), true);
+        let error = core.run(future).unwrap_err().to_string();
+        assert!(error.starts_with("Failed to spawn solver"));
+    }
+
+    #[test]
+    fn command_builder() {
+        let jar = SolverCommand::new("solver.jar")
+            .jvm_arg("-Xmx4g")
+            .solver_arg("--algo=bl");
+        assert_eq!(jar.program(), "java");
+        assert_eq!(jar.args(), ["-Xmx4g", "-jar", "solver.jar", "--algo=bl"]);
+
+        let binary = SolverCommand::new("./solver")
+            .jvm_arg("-Xmx4g")
+            .solver_arg("--algo=bl");
+        assert_eq!(binary.program(), "./solver");
+        assert_eq!(binary.args(), ["--algo=bl"]);
+    }
+
+    #[test]
+    fn command_with_extra_args() {
+        let jvm_args = ["-Xmx4g".to_string(), "-Xss16m".to_string()];
+        let program_args = ["--algo=bl".to_string()];
+        let command = command_with_args(&"solver.jar".into(), &jvm_args, &program_args);
+        assert_eq!(command.program(), "java");
+        assert_eq!(
+            command.args(),
+            ["-Xmx4g", "-Xss16m", "-jar", "solver.jar", "--algo=bl"]
+        );
+    }
+
+    #[test]
+    fn max_memory() {
+        let output = SolverCommand::new("/bin/sh")
+            .solver_arg("-c")
+            .solver_arg("ulimit -v")
+            .max_memory(512 << 20)
+            .build()
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "524288");
+    }
 }
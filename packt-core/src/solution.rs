@@ -1,105 +1,1168 @@
 use failure::Error;
-use geometry::{Placement, Point, Rectangle, Rotation::*};
+use geometry::{Placement, Point, Rectangle, Rotation, Rotation::*};
 use problem::{Problem, Variant};
+use serde_json;
+use std::cmp::Ordering;
 use std::fmt::{self, Formatter};
+use std::fs::File;
+use std::io::{BufRead, Read};
 use std::iter;
+use std::path::Path;
 use std::result;
 use std::str::FromStr;
 use std::time::Duration;
 
 type Result<T, E = Error> = result::Result<T, E>;
 
-#[derive(Clone, Debug, PartialEq)]
+/// The largest container area for which evaluations include the fragmentation, if asked for
+/// with `Solution::measure_fragmentation`, as computing it takes memory linear in the area.
+pub const MAX_FRAGMENTATION_AREA: u64 = 10_000_000;
+
+/// The placement lines that solvers commonly use for a rectangle they did not place, compared
+/// after collapsing whitespace.
+pub const DEFAULT_UNPLACED: &[&str] = &["- -", "skip"];
+
+#[derive(Clone, Debug)]
 pub struct Solution {
     variant: Variant,
     allow_rotation: bool,
+    /// Whether `evaluate` accepts unplaced rectangles. See `accept_partial`.
+    partial: bool,
+    /// Whether evaluations include the fragmentation. See `measure_fragmentation`.
+    with_fragmentation: bool,
+    /// Whether the container is cut to the placements instead of starting at the origin. See
+    /// `tighten`.
+    tight: bool,
+    /// The rectangles the solver left unplaced, with their index in the problem.
+    unplaced: Vec<(usize, Rectangle)>,
+    /// The running time the solver printed itself, if any. See `report_duration`.
+    reported_duration: Option<Duration>,
+    /// The result of the last successful `evaluate`, with the margin it used. Cleared by
+    /// everything that changes the placements or the container.
+    evaluation: Option<(u32, Evaluation)>,
     source: Option<Problem>,
+    /// The placed rectangles only. Unplaced ones are kept apart in `unplaced`, rather than as
+    /// `None` slots in here, since almost everything, from the container to the overlap
+    /// checks, only looks at placed rectangles. `placement_slots` merges both.
     placements: Vec<Placement>,
 }
 
 impl Solution {
+    /// Creates a solution for `problem`, where `placements` are given in the same order as the
+    /// rectangles of the problem.
+    pub fn new(problem: Problem, placements: Vec<Placement>) -> Solution {
+        Solution {
+            variant: problem.variant,
+            allow_rotation: problem.allow_rotation,
+            partial: false,
+            with_fragmentation: false,
+            tight: false,
+            reported_duration: None,
+            evaluation: None,
+            unplaced: Vec::new(),
+            source: Some(problem),
+            placements,
+        }
+    }
+
+    /// Creates a solution for `problem` without any placements, as a starting point for
+    /// placing rectangles one by one.
+    pub fn empty(problem: Problem) -> Solution {
+        Solution::new(problem, Vec::new())
+    }
+
+    /// The placed rectangles, in the order of the problem, leaving out unplaced ones.
+    pub fn placements(&self) -> &[Placement] {
+        &self.placements
+    }
+
+    pub fn placement_count(&self) -> usize {
+        self.placements.len()
+    }
+
+    /// The number of placements that are rotated.
+    pub fn rotated_count(&self) -> usize {
+        self.placements
+            .iter()
+            .filter(|p| p.rotation == Rotated)
+            .count()
+    }
+
+    /// The number of rectangles the solver left unplaced, see `DEFAULT_UNPLACED`.
+    pub fn unplaced_count(&self) -> usize {
+        self.unplaced.len()
+    }
+
+    /// The placement of every rectangle in the order of the problem, with `None` for the
+    /// rectangles that were not placed.
+    pub fn placement_slots(&self) -> Vec<Option<Placement>> {
+        let mut placements = self.placements.iter();
+        let mut unplaced = self.unplaced.iter().peekable();
+        (0..self.placements.len() + self.unplaced.len())
+            .map(|i| match unplaced.peek() {
+                Some(&&(j, _)) if j == i => {
+                    unplaced.next();
+                    None
+                }
+                _ => placements.next().cloned(),
+            })
+            .collect()
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn allow_rotation(&self) -> bool {
+        self.allow_rotation
+    }
+
     /// Checks whether this solution is valid.
     ///
     /// # Complexity
     ///
     /// Takes quadratic (in `self.placements.len()`) time.
     pub fn is_valid(&self) -> bool {
-        if let Some((p1, p2)) = self
-            .placements
-            .iter()
-            .enumerate()
-            .flat_map(|(i, p)| iter::repeat(p).zip(self.placements.iter().skip(i + 1)))
-            .find(|(p1, p2)| p1.overlaps(p2))
-        {
-            eprintln!("Overlap found: {:#?} and {:#?}", p1, p2);
+        self.is_valid_with_margin(0)
+    }
+
+    /// Checks whether every pair of placements is separated by at least `margin` units, as
+    /// required when pieces are cut with a saw of kerf `margin`.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn is_valid_with_margin(&self, margin: u32) -> bool {
+        if let Some((p1, p2)) = self.find_pair(|p1, p2| p1.within_margin(p2, margin)) {
+            debug!(
+                "Overlap found within margin {}: {:#?} and {:#?}",
+                margin, p1, p2
+            );
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Like `is_valid`, but permits placements to overlap by at most `tolerance` units in
+    /// either direction, as produced by solvers that round floating point coordinates.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn is_valid_with_tolerance(&self, tolerance: u32) -> bool {
+        if let Some((p1, p2)) = self.find_pair(|p1, p2| p1.overlaps_beyond(p2, tolerance)) {
+            debug!(
+                "Overlap found beyond tolerance {}: {:#?} and {:#?}",
+                tolerance, p1, p2
+            );
             false
         } else {
             true
         }
     }
 
+    /// Finds the first pair of distinct placements for which `f` holds.
+    fn find_pair<F>(&self, f: F) -> Option<(&Placement, &Placement)>
+    where
+        F: Fn(&Placement, &Placement) -> bool,
+    {
+        self.placements
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| iter::repeat(p).zip(self.placements.iter().skip(i + 1)))
+            .find(|(p1, p2)| f(p1, p2))
+    }
+
+    /// Checks that every placement has positive dimensions and covers exactly its rectangle
+    /// on the integer grid, without running out of the coordinate range.
+    ///
+    /// Coordinates are integers by construction, so this only guards against placements
+    /// whose corners were set by hand or by a parser that does not go through
+    /// `Placement::new`. Both `evaluate` and `evaluate_lenient` check this first.
+    pub fn assert_integral_grid(&self) -> Result<()> {
+        for (i, p) in self.placements.iter().enumerate() {
+            let (width, height) = (p.width(), p.height());
+            if width == 0 || height == 0 {
+                bail!(
+                    "Placement {} has a zero dimension: {} by {}",
+                    i,
+                    width,
+                    height
+                )
+            }
+
+            let Point { x, y } = p.bottom_left;
+            let top_right = match (x.checked_add(width - 1), y.checked_add(height - 1)) {
+                (Some(x), Some(y)) => Some(Point::new(x, y)),
+                _ => None,
+            };
+            if top_right != Some(p.top_right) {
+                bail!(
+                    "Placement {} at ({}, {}) does not cover its {} by {} rectangle",
+                    i,
+                    x,
+                    y,
+                    width,
+                    height
+                )
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates this solution, failing if it is invalid or, unless `accept_partial` was
+    /// called, if any rectangle was left unplaced.
     pub fn evaluate(&mut self, duration: Duration) -> Result<Evaluation> {
-        if !self.is_valid() {
-            bail!("Overlap in solution")
+        self.evaluate_with_margin(duration, 0)
+    }
+
+    /// Like `evaluate`, but rejects placements closer than `margin` units to each other.
+    ///
+    /// The evaluation is cached, so evaluating an unchanged solution again with the same
+    /// margin only replaces the duration.
+    pub fn evaluate_with_margin(&mut self, duration: Duration, margin: u32) -> Result<Evaluation> {
+        if let Some((cached_margin, evaluation)) = self.evaluation {
+            if cached_margin == margin {
+                return Ok(Evaluation {
+                    duration,
+                    ..evaluation
+                });
+            }
         }
 
-        let container = self.container()?;
-        let min_area = self.placements.iter_mut().map(|p| p.rectangle.area()).sum();
-        let empty_area = container.area() as i64 - min_area as i64;
-        let filling_rate = (min_area as f64 / container.area() as f64) as f32;
+        self.assert_integral_grid()?;
+        if !self.partial && !self.unplaced.is_empty() {
+            bail!(
+                "{} rectangles were not placed, the first one is rectangle {}",
+                self.unplaced.len(),
+                self.unplaced[0].0
+            )
+        }
+        if !self.is_valid_with_margin(margin) {
+            if margin == 0 {
+                bail!("Overlap in solution")
+            } else {
+                bail!("Overlap in solution with a margin of {}", margin)
+            }
+        }
 
-        if filling_rate > 1.0 {
+        let evaluation = self.evaluation(self.container()?, duration, 0)?;
+        if evaluation.filling_rate > 1.0 {
             bail!("Undetected overlap in solution")
         }
 
+        self.evaluation = Some((margin, evaluation));
+        Ok(evaluation)
+    }
+
+    /// Evaluates this solution without rejecting overlapping placements, recording the total
+    /// area of all pairwise overlaps instead. Likewise, placements sticking out above the
+    /// fixed height are counted rather than rejected.
+    ///
+    /// The filling rate of an overlapping or out of bounds solution may exceed 1. Unplaced
+    /// rectangles are accepted, as if `accept_partial` was called.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn evaluate_lenient(&mut self, duration: Duration) -> Result<Evaluation> {
+        self.assert_integral_grid()?;
+
+        let total_overlap_area = self
+            .placements
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| iter::repeat(p).zip(self.placements.iter().skip(i + 1)))
+            .map(|(p1, p2)| p1.intersection_area(p2))
+            .sum();
+
+        self.evaluation(self.bounding_container(true)?, duration, total_overlap_area)
+    }
+
+    fn evaluation(
+        &self,
+        container: Rectangle,
+        duration: Duration,
+        total_overlap_area: u64,
+    ) -> Result<Evaluation> {
+        let min_area = self.placements.iter().map(Placement::area).sum();
+        let empty_area = container.area() as i64 - min_area as i64;
+        let filling_rate = (min_area as f64 / container.area() as f64) as f32;
+        let measurable = container.area() <= MAX_FRAGMENTATION_AREA;
+        let fragmentation = if self.with_fragmentation && measurable {
+            self.fragmentation().ok()
+        } else {
+            None
+        };
+
+        let occupied = self.occupied_bounds();
+        let height_utilization = occupied.height as f32 / container.height as f32;
+        let container_perimeter = 2 * (u64::from(container.width) + u64::from(container.height));
+
         Ok(Evaluation {
             container,
+            occupied,
             min_area,
             empty_area,
             filling_rate,
             duration,
+            total_overlap_area,
+            out_of_bounds_count: self.out_of_bounds().len(),
+            unplaced_count: self.unplaced.len(),
+            height_utilization,
+            container_perimeter,
+            rotations_used: self.rotated_count(),
+            fragmentation,
+            efficiency: self.efficiency(),
+            phases: None,
+            solver_reported_duration: self.reported_duration,
         })
     }
 
 
     pub fn container(&self) -> Result<Rectangle> {
-        use std::cmp::max;
+        self.bounding_container(false)
+    }
 
-        let (x, y) = self.placements.iter().fold((0, 0), |(x, y), p| {
-            let tr = p.top_right;
-            let x = max(x, tr.x);
-            let y = max(y, tr.y);
-            (x, y)
-        });
+    /// The smallest rectangle enclosing all placements, whatever the variant. For the fixed
+    /// variant this may be lower than the container, which always has the fixed height.
+    ///
+    /// Unlike `container`, this does not start at the origin and never fails; it is empty if
+    /// there are no placements.
+    pub fn occupied_bounds(&self) -> Rectangle {
+        Rectangle::bounding_of(self.corners())
+    }
+
+    /// The bottom-left corner of the container: the origin, unless the solution was tightened.
+    pub fn container_origin(&self) -> Point {
+        if !self.tight || self.placements.is_empty() {
+            return Point::new(0, 0);
+        }
+
+        let min = |f: fn(&Placement) -> u32| self.placements.iter().map(f).min().unwrap_or(0);
+        match self.variant {
+            Variant::Fixed(_) => Point::new(min(|p| p.bottom_left.x), 0),
+            Variant::Free => Point::new(min(|p| p.bottom_left.x), min(|p| p.bottom_left.y)),
+        }
+    }
+
+    /// The smallest rectangle from the container origin enclosing all placements. Empty if
+    /// there are no placements.
+    fn bounds_from_origin(&self) -> Rectangle {
+        if self.placements.is_empty() {
+            return Rectangle::new(0, 0);
+        }
+
+        Rectangle::bounding_of(self.corners().chain(iter::once(self.container_origin())))
+    }
+
+    /// The bottom-left and top-right corners of all placements.
+    fn corners<'a>(&'a self) -> impl Iterator<Item = Point> + 'a {
+        let bottom_left = self.placements.iter().map(|p| p.bottom_left);
+        bottom_left.chain(self.placements.iter().map(|p| p.top_right))
+    }
 
-        let (x, y) = (x + 1, y + 1);
+    /// Like `container`, but if `lenient` is set, placements above the fixed height do not
+    /// make it fail. The container then still has the fixed height.
+    fn bounding_container(&self, lenient: bool) -> Result<Rectangle> {
+        if self.placements.is_empty() {
+            bail!("Solution contains no placements")
+        }
+
+        let bounds = self.bounds_from_origin();
 
-        let p = self.source.as_ref().unwrap();
-        let container = match p.variant {
-            Variant::Fixed(k) if y > k => bail!(
-                "Solution placements exceed problem bounds: top: {}, bound: {}",
-                y,
-                k
-            ),
-            Variant::Fixed(k) => Rectangle::new(x, k),
-            _ => Rectangle::new(x, y),
+        let container = match self.variant {
+            Variant::Fixed(k) if !lenient && !self.out_of_bounds().is_empty() => {
+                let overshoots: Vec<String> = self
+                    .out_of_bounds()
+                    .into_iter()
+                    .map(|(i, overshoot)| format!("placement {} by {}", i, overshoot))
+                    .collect();
+                bail!(
+                    "Solution placements exceed the fixed height {}: {}",
+                    k,
+                    overshoots.join(", ")
+                )
+            }
+            Variant::Fixed(k) => Rectangle::new(bounds.width, k),
+            _ => bounds,
         };
 
         Ok(container)
     }
 
+    /// Lists every placement that sticks out above the fixed height, as its index and the
+    /// number of units it overshoots by. Always empty for the free variant.
+    pub fn out_of_bounds(&self) -> Vec<(usize, u32)> {
+        match self.variant {
+            Variant::Fixed(k) => self
+                .placements
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.top_right.y >= k)
+                .map(|(i, p)| (i, p.top_right.y + 1 - k))
+                .collect(),
+            Variant::Free => Vec::new(),
+        }
+    }
+
+    /// Counts the maximal empty rectangles in the container: empty areas that cannot be
+    /// extended in any direction. This is a proxy for how fragmented the unused space is;
+    /// a single large gap counts as one, while the same area scattered over many small gaps
+    /// counts many times.
+    ///
+    /// # Complexity
+    ///
+    /// Takes linear time and memory in the area of the container, so it fails for containers
+    /// larger than `MAX_FRAGMENTATION_AREA`.
+    pub fn fragmentation(&self) -> Result<usize> {
+        let grid = self.grid()?;
+        let width = grid.first().map_or(0, Vec::len);
+        let mut heights = vec![0; width + 1];
+        let mut filled_below = vec![0; width + 1];
+        let mut count = 0;
+
+        for y in 0..grid.len() {
+            // The number of filled cells in row y - 1 left of every column, to tell in constant
+            // time whether an empty run is blocked from extending downwards
+            if y > 0 {
+                for x in 0..width {
+                    filled_below[x + 1] = filled_below[x] + grid[y - 1][x] as usize;
+                }
+            }
+
+            // The number of empty cells from row y upwards, per column
+            for x in 0..width {
+                heights[x] = if grid[y][x] {
+                    0
+                } else if y > 0 && !grid[y - 1][x] {
+                    heights[x] - 1
+                } else {
+                    (y..grid.len()).take_while(|&y| !grid[y][x]).count()
+                };
+            }
+
+            // Every maximal horizontal run at every height, found with a monotonic stack
+            let mut stack: Vec<(usize, usize)> = Vec::new();
+            for x in 0..=width {
+                let mut start = x;
+                while let Some(&(s, h)) = stack.last() {
+                    if h <= heights[x] {
+                        break;
+                    }
+
+                    stack.pop();
+                    start = s;
+                    let blocked_below = y == 0 || filled_below[x] > filled_below[s];
+                    if blocked_below {
+                        count += 1;
+                    }
+                }
+
+                if heights[x] > 0 && stack.last().map_or(true, |&(_, h)| h < heights[x]) {
+                    stack.push((start, heights[x]));
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Counts how many placements cover every `cell` by `cell` bin of the container, row by
+    /// row from the bottom. Bins at the top and right edge are partially outside the
+    /// container if its dimensions are not divisible by `cell`. Counts above 1 indicate
+    /// overlap.
+    pub fn occupancy_grid(&self, cell: u32) -> Vec<Vec<u8>> {
+        let cell = cell.max(1);
+        let (width, height) = self.extent();
+        let origin = self.container_origin();
+        let bins = |n: u32| ((n + cell - 1) / cell) as usize;
+
+        let mut grid = vec![vec![0u8; bins(width)]; bins(height)];
+        for p in &self.placements {
+            let (bottom, top) = (p.bottom_left.y - origin.y, p.top_right.y - origin.y);
+            let (left, right) = (p.bottom_left.x - origin.x, p.top_right.x - origin.x);
+            let (bottom, top) = (bottom / cell, top / cell);
+            let (left, right) = (left / cell, right / cell);
+            for row in &mut grid[bottom as usize..=top as usize] {
+                for count in &mut row[left as usize..=right as usize] {
+                    *count = count.saturating_add(1);
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// The width and height of the area from the container origin that contains all
+    /// placements, and the fixed height if there is one.
+    fn extent(&self) -> (u32, u32) {
+        let Rectangle { width, height: top } = self.bounds_from_origin();
+        let height = self.variant.fixed_height().map_or(top, |k| k.max(top));
+
+        (width, height)
+    }
+
+    /// Marks the cells covered by placements, row by row from the bottom of the container.
+    fn grid(&self) -> Result<Vec<Vec<bool>>> {
+        // Fails on empty solutions and on placements above a fixed height
+        self.container()?;
+
+        let (width, height) = self.extent();
+        let area = u64::from(width) * u64::from(height);
+        if area > MAX_FRAGMENTATION_AREA {
+            bail!(
+                "Cannot measure a {}x{} container, the limit is an area of {}",
+                width,
+                height,
+                MAX_FRAGMENTATION_AREA
+            );
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        let origin = self.container_origin();
+        let mut grid = vec![vec![false; width]; height];
+        for p in &self.placements {
+            let (bottom, top) = (p.bottom_left.y - origin.y, p.top_right.y - origin.y);
+            let (left, right) = (p.bottom_left.x - origin.x, p.top_right.x - origin.x);
+            for row in &mut grid[bottom as usize..=top as usize] {
+                for cell in &mut row[left as usize..=right as usize] {
+                    *cell = true;
+                }
+            }
+        }
+
+        Ok(grid)
+    }
+
+    /// Cuts the container to the placements, dropping any unused space to the left of and
+    /// below them, so `evaluate` reports the tightest honest filling rate. For the fixed
+    /// variant only the width is cut, as the height is given.
+    ///
+    /// This only changes the derived container, see `container_origin`; the placements are
+    /// kept as they are.
+    pub fn tighten(&mut self) {
+        self.tight = true;
+        self.evaluation = None;
+    }
+
+    /// A copy of this solution in a canonical position, so that solutions that only differ by
+    /// symmetry compare equal.
+    ///
+    /// Normalizes translations, by moving the bounding box of the placements to the origin,
+    /// and reflections in the horizontal and vertical axis, by picking whichever reflection of
+    /// the bounding box gives the lexicographically smallest list of bottom-left corners, in
+    /// rectangle order. Rotations and transpositions are not normalized, as they would change
+    /// the height of a fixed container, and neither are swaps of identical rectangles.
+    pub fn canonical_form(&self) -> Solution {
+        let Rectangle { width, height } = self.occupied_bounds();
+        let min = |f: fn(&Placement) -> u32| self.placements.iter().map(f).min().unwrap_or(0);
+        let (x_min, y_min) = (min(|p| p.bottom_left.x), min(|p| p.bottom_left.y));
+
+        let reflect = |p: &Placement, (flip_x, flip_y): (bool, bool)| {
+            let x = if flip_x {
+                width - 1 - (p.top_right.x - x_min)
+            } else {
+                p.bottom_left.x - x_min
+            };
+            let y = if flip_y {
+                height - 1 - (p.top_right.y - y_min)
+            } else {
+                p.bottom_left.y - y_min
+            };
+
+            Placement::new(p.rectangle, p.rotation, Point::new(x, y))
+        };
+
+        let placements = [(false, false), (true, false), (false, true), (true, true)]
+            .iter()
+            .map(|&flip| {
+                self.placements
+                    .iter()
+                    .map(|p| reflect(p, flip))
+                    .collect::<Vec<_>>()
+            })
+            .min_by_key(|placements| {
+                placements
+                    .iter()
+                    .map(|p| (p.bottom_left.x, p.bottom_left.y))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        Solution {
+            placements,
+            evaluation: None,
+            ..self.clone()
+        }
+    }
+
+    /// Lets `evaluate` accept a solution in which some rectangles were not placed. Their area
+    /// is left out of the evaluation, so the filling rate only covers the placed rectangles.
+    pub fn accept_partial(&mut self) {
+        self.partial = true;
+        self.evaluation = None;
+    }
+
+    /// Records the running time the solver printed itself, which evaluations then include as
+    /// `solver_reported_duration`.
+    pub fn report_duration(&mut self, duration: Duration) {
+        self.reported_duration = Some(duration);
+        self.evaluation = None;
+    }
+
+    /// Lets `evaluate` include the fragmentation of the empty space. It is left out by default,
+    /// as it takes time and memory linear in the area of the container, and even then only
+    /// computed for containers of at most `MAX_FRAGMENTATION_AREA`.
+    pub fn measure_fragmentation(&mut self) {
+        self.with_fragmentation = true;
+        self.evaluation = None;
+    }
+
     pub fn source(&mut self, p: Problem) {
         self.source = Some(p);
+        self.evaluation = None;
+    }
+
+    /// The area of the container the problem was generated from, divided by the area of the
+    /// container of this solution. This is at most 1 for valid solutions, and 1 if the
+    /// solution is optimal.
+    ///
+    /// Only known for generated problems, which carry their source container.
+    pub fn efficiency(&self) -> Option<f32> {
+        let optimal = self.source.as_ref()?.source?;
+        let container = self.container().ok()?;
+        Some((optimal.area() as f64 / container.area() as f64) as f32)
     }
+
+    /// Rotates individual placements in place wherever that shrinks the container without
+    /// introducing overlaps, returning how many placements were rotated.
+    ///
+    /// Rotations that would not reduce the container area are left alone, so the container
+    /// never grows. Does nothing if the problem does not allow rotation.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn try_rotate_improvements(&mut self) -> usize {
+        if !self.allow_rotation {
+            return 0;
+        }
+
+        self.evaluation = None;
+        let mut rotated = 0;
+        for i in 0..self.placements.len() {
+            let area = match self.container() {
+                Ok(container) => container.area(),
+                Err(_) => break,
+            };
+
+            let old = self.placements[i];
+            let rotation = match old.rotation {
+                Normal => Rotated,
+                Rotated => Normal,
+            };
+            let new = Placement::new(old.rectangle, rotation, old.bottom_left);
+
+            let overlaps = self
+                .placements
+                .iter()
+                .enumerate()
+                .any(|(j, p)| j != i && p.overlaps(&new));
+            if overlaps {
+                continue;
+            }
+
+            self.placements[i] = new;
+            match self.container() {
+                Ok(ref container) if container.area() < area => rotated += 1,
+                _ => self.placements[i] = old,
+            }
+        }
+
+        rotated
+    }
+
+    /// Counts the pairs of placements that share part of an edge without overlapping. Relative
+    /// to the number of placements, this shows how compact the packing is. Placements that
+    /// only meet at a corner are not counted.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn adjacency_count(&self) -> usize {
+        self.placements
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| iter::repeat(p).zip(self.placements.iter().skip(i + 1)))
+            .filter(|(p1, p2)| p1.abuts(p2))
+            .count()
+    }
+
+    /// The total length of the placement edges that border empty space, i.e. that are shared
+    /// with neither another placement nor the boundary of the container. Like the adjacency
+    /// count this shows how compact the packing is; it is 0 for a perfect packing. Only
+    /// meaningful for solutions without overlap.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn exposed_perimeter(&self) -> u64 {
+        let container = match self.bounding_container(true) {
+            Ok(container) => container,
+            Err(_) => return 0,
+        };
+        let origin = self.container_origin();
+        let (right, top) = (origin.x + container.width, origin.y + container.height);
+
+        let perimeter: u64 = self
+            .placements
+            .iter()
+            .map(|p| 2 * (u64::from(p.width()) + u64::from(p.height())))
+            .sum();
+        let on_boundary: u64 = self
+            .placements
+            .iter()
+            .map(|p| {
+                let (width, height) = (u64::from(p.width()), u64::from(p.height()));
+                let mut length = 0;
+                if p.bottom_left.x == origin.x {
+                    length += height;
+                }
+                if p.top_right.x + 1 == right {
+                    length += height;
+                }
+                if p.bottom_left.y == origin.y {
+                    length += width;
+                }
+                if p.top_right.y + 1 == top {
+                    length += width;
+                }
+                length
+            })
+            .sum();
+        let shared: u64 = self
+            .placements
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| iter::repeat(p).zip(self.placements.iter().skip(i + 1)))
+            .map(|(p1, p2)| u64::from(p1.shared_edge(p2)))
+            .sum();
+
+        perimeter.saturating_sub(on_boundary + 2 * shared)
+    }
+
+    /// Groups the placements into clusters of placements that touch each other, directly or
+    /// through other placements of the same cluster.
+    ///
+    /// Every cluster lists its placement indices in ascending order, and clusters are ordered
+    /// by their first index.
+    ///
+    /// # Complexity
+    ///
+    /// Takes quadratic (in `self.placements.len()`) time.
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        fn find(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        let n = self.placements.len();
+        let mut parents: Vec<usize> = (0..n).collect();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                if self.placements[i].touches(&self.placements[j]) {
+                    let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                    parents[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        let mut roots: Vec<Option<usize>> = vec![None; n];
+        for i in 0..n {
+            let root = find(&mut parents, i);
+            match roots[root] {
+                Some(c) => components[c].push(i),
+                None => {
+                    roots[root] = Some(components.len());
+                    components.push(vec![i]);
+                }
+            }
+        }
+
+        components
+    }
+
+    /// Adds `p` to the placements of this solution, provided it does not overlap any of the
+    /// placements already present.
+    ///
+    /// # Complexity
+    ///
+    /// Takes linear (in `self.placements.len()`) time.
+    pub fn try_place(&mut self, p: Placement) -> Result<()> {
+        if let Some(other) = self.placements.iter().find(|other| other.overlaps(&p)) {
+            bail!("Overlap found: {:?} and {:?}", other, p)
+        }
+
+        self.placements.push(p);
+        self.evaluation = None;
+        Ok(())
+    }
+
+    /// Formats this solution like solver output, annotating every placement with the index
+    /// and dimensions of its rectangle, e.g. `0 0  # rect 0: 12 8`. Unplaced rectangles are
+    /// written as the first of `DEFAULT_UNPLACED`.
+    ///
+    /// If the source problem is unknown, it is reconstructed from the placements.
+    pub fn to_string_annotated(&self) -> String {
+        let slots = self.placement_slots();
+        let problem = self.source.clone().unwrap_or_else(|| {
+            let mut rectangles: Vec<_> = self.placements.iter().map(|p| p.rectangle).collect();
+            for &(i, r) in &self.unplaced {
+                rectangles.insert(i, r);
+            }
+
+            Problem {
+                variant: self.variant,
+                allow_rotation: self.allow_rotation,
+                rectangles,
+                source: None,
+                name: None,
+                truncated: false,
+                palette: None,
+            }
+        });
+
+        let mut output = format!("{}\nplacement of rectangles", problem);
+        for (i, (slot, r)) in slots.iter().zip(problem.rectangles()).enumerate() {
+            let p = match slot {
+                Some(p) => p,
+                None => {
+                    output.push_str(&format!("\n{}  # rect {}: {}", DEFAULT_UNPLACED[0], i, r));
+                    continue;
+                }
+            };
+
+            output.push_str(&format!(
+                "\n{}  # rect {}: {}",
+                self.placement_line(p),
+                i,
+                p.rectangle
+            ));
+        }
+
+        output
+    }
+
+    /// Formats only the `placement of rectangles` section of solver output, without the
+    /// problem, as some graders expect. Unplaced rectangles are written as the first of
+    /// `DEFAULT_UNPLACED`.
+    pub fn to_placements_only(&self) -> String {
+        let mut output = String::from("placement of rectangles");
+        for slot in self.placement_slots() {
+            output.push('\n');
+            match slot {
+                Some(p) => output.push_str(&self.placement_line(&p)),
+                None => output.push_str(DEFAULT_UNPLACED[0]),
+            }
+        }
+
+        output
+    }
+
+    /// Formats `p` as a line of solver output, e.g. `yes 12 0`.
+    fn placement_line(&self, p: &Placement) -> String {
+        let rotation = match (self.allow_rotation, p.rotation) {
+            (false, _) => "",
+            (true, Normal) => "no ",
+            (true, Rotated) => "yes ",
+        };
+
+        format!("{}{} {}", rotation, p.bottom_left.x, p.bottom_left.y)
+    }
+
+    /// Parses a solution for `problem` in JSON, for solvers that do not output the text format.
+    ///
+    /// The placements are given in the same order as the rectangles of the problem, e.g.
+    /// `{"placements": [{"x": 0, "y": 0, "rotated": false}]}`. `rotated` may be left out, and
+    /// a `null` placement marks its rectangle as not placed.
+    pub fn from_json(problem: &Problem, s: &str) -> Result<Solution> {
+        let JsonSolution { placements } = serde_json::from_str(s)?;
+        if placements.len() != problem.rectangle_count() {
+            bail!("Solution contains a different number of placements than rectangles");
+        }
+
+        let unplaced = placements
+            .iter()
+            .zip(problem.rectangles())
+            .enumerate()
+            .filter(|(_, (p, _))| p.is_none())
+            .map(|(i, (_, &r))| (i, r))
+            .collect();
+
+        let placements = placements
+            .into_iter()
+            .zip(problem.rectangles())
+            .filter_map(|(p, r)| p.map(|p| (p, r)))
+            .map(|(p, &r)| {
+                let rotation = match (problem.allow_rotation, p.rotated) {
+                    (_, false) => Normal,
+                    (true, true) => Rotated,
+                    (false, true) => bail!("Rotated placement in a problem without rotations"),
+                };
+
+                Ok(Placement::new(r, rotation, Point::new(p.x, p.y)))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Solution {
+            unplaced,
+            ..Solution::new(problem.clone(), placements)
+        })
+    }
+
+    /// Builds a solution for `problem` from solver output as it arrives, validating every
+    /// placement against the ones read before it.
+    ///
+    /// Lines up to and including the `placement of rectangles` marker are skipped, so the
+    /// solver may echo the problem, and lines in `DEFAULT_UNPLACED` mark their rectangle as not
+    /// placed. Fails on the first line that cannot be parsed or that overlaps an earlier
+    /// placement, reporting its line number.
+    pub fn from_stream<R: BufRead>(problem: Problem, reader: R) -> Result<Solution> {
+        let mut solution = Solution {
+            variant: problem.variant,
+            allow_rotation: problem.allow_rotation,
+            partial: false,
+            with_fragmentation: false,
+            tight: false,
+            reported_duration: None,
+            evaluation: None,
+            unplaced: Vec::new(),
+            source: None,
+            placements: Vec::with_capacity(problem.rectangle_count()),
+        };
+
+        let mut rectangles = problem.rectangles().iter().enumerate();
+        let mut in_placements = false;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if !in_placements {
+                in_placements = line.trim() == "placement of rectangles";
+                continue;
+            } else if line.trim().is_empty() {
+                continue;
+            }
+
+            let (index, &r) = rectangles
+                .next()
+                .ok_or_else(|| format_err!("line {}: more placements than rectangles", i + 1))?;
+            if is_sentinel(&line, DEFAULT_UNPLACED) {
+                solution.unplaced.push((index, r));
+                continue;
+            }
+
+            let (rotation, coord) = parse_placement(solution.allow_rotation, &line)
+                .map_err(|e| format_err!("line {}: {}", i + 1, e))?;
+            solution
+                .try_place(Placement::new(r, rotation, coord))
+                .map_err(|e| format_err!("line {}: {}", i + 1, e))?;
+        }
+
+        if rectangles.next().is_some() {
+            bail!("Solution contains a different number of placements than rectangles");
+        }
+
+        solution.source(problem);
+        Ok(solution)
+    }
+}
+
+/// Checks whether the placement line `s` is one of `sentinels`, ignoring a trailing `#`
+/// comment and repeated whitespace.
+fn is_sentinel(s: &str, sentinels: &[&str]) -> bool {
+    let s = s.split('#').next().unwrap_or("");
+    let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    sentinels.iter().any(|sentinel| *sentinel == s)
+}
+
+/// Parses a placement line, ignoring a trailing `#` comment. A bare `x y` line is accepted even
+/// if rotations are allowed, in which case the rectangle is not rotated.
+fn parse_placement(allow_rotation: bool, s: &str) -> Result<(Rotation, Point)> {
+    let s = s.split('#').next().unwrap_or("");
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    let coordinate = |token: &str| {
+        token
+            .parse::<u32>()
+            .map_err(|_| format_err!("Invalid coordinate '{}'", token))
+    };
+
+    let result = match (allow_rotation, tokens.as_slice()) {
+        (_, [x, y]) => {
+            let p = Point::new(coordinate(x)?, coordinate(y)?);
+            (Normal, p)
+        }
+        (true, [rot, x, y]) => {
+            let p = Point::new(coordinate(x)?, coordinate(y)?);
+            (rot.parse()?, p)
+        }
+        _ => bail!("Invalid format: {}", tokens.join(" ")),
+    };
+
+    Ok(result)
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Evaluation {
     pub container: Rectangle,
+    /// The box actually enclosing the placements, see `Solution::occupied_bounds`. Its height
+    /// is below that of the container if a fixed height is not used up.
+    pub occupied: Rectangle,
     pub min_area: u64,
     pub empty_area: i64,
     pub filling_rate: f32,
     pub duration: Duration,
+    /// The summed area of all overlapping placement pairs, which is 0 unless the solution
+    /// was evaluated leniently.
+    pub total_overlap_area: u64,
+    /// The number of placements sticking out above the fixed height, which is 0 unless the
+    /// solution was evaluated leniently. The free variant has no bounds to exceed.
+    pub out_of_bounds_count: usize,
+    /// The number of rectangles the solver left unplaced, which is 0 unless partial solutions
+    /// are accepted. Their area is not part of `min_area`.
+    pub unplaced_count: usize,
+    /// The fraction of the container height spanned by the placements, i.e. the height of
+    /// `occupied` over that of `container`. For the fixed variant, 1 means the packing reaches
+    /// from the bottom to the top of the strip, while a lower value means the solver packed
+    /// into a shorter strip than it was given. Only exceeds 1 for out of bounds placements.
+    pub height_utilization: f32,
+    /// The perimeter of the container, `2 * (width + height)`. Relative to the area of the
+    /// container, this is lowest for a square one.
+    pub container_perimeter: u64,
+    /// The number of rectangles placed rotated.
+    pub rotations_used: usize,
+    /// The number of maximal empty rectangles, see `Solution::fragmentation`. Only computed
+    /// if asked for with `Solution::measure_fragmentation`, for containers of at most
+    /// `MAX_FRAGMENTATION_AREA`.
+    pub fragmentation: Option<usize>,
+    /// How close the container is to the optimal one, see `Solution::efficiency`.
+    pub efficiency: Option<f32>,
+    /// How the duration breaks down, if the solution was produced by the runner.
+    pub phases: Option<Phases>,
+    /// The running time the solver printed as a `time: N.NNN` line, in seconds. Unlike
+    /// `duration` this excludes JVM startup.
+    pub solver_reported_duration: Option<Duration>,
+}
+
+/// Time spent in the different phases of a single solver run.
+///
+/// `write` and `wait` together make up the `duration` of the run; `spawn` and `parse` are
+/// not counted towards it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Phases {
+    /// Starting the solver process, including opening its stdin.
+    pub spawn: Duration,
+    /// Writing the problem to the solver.
+    pub write: Duration,
+    /// Waiting for the solver to finish.
+    pub wait: Duration,
+    /// Parsing the solver output.
+    pub parse: Duration,
+}
+
+impl Evaluation {
+    /// Whether the filling rate is at least `threshold`, i.e. the solution is good enough.
+    pub fn meets_threshold(&self, threshold: f32) -> bool {
+        self.filling_rate >= threshold
+    }
+
+    /// Combines quality and speed into a single score: the filling rate minus `time_weight`
+    /// for every second the solver took.
+    pub fn weighted_score(&self, time_weight: f64) -> f64 {
+        f64::from(self.filling_rate) - time_weight * secs(self.duration)
+    }
+
+    /// Checks whether this evaluation should be preferred over `other`.
+    ///
+    /// Prefers the higher filling rate, then the smaller container, then the shorter duration.
+    pub fn better_than(&self, other: &Evaluation) -> bool {
+        if self.filling_rate != other.filling_rate {
+            self.filling_rate > other.filling_rate
+        } else if self.container.area() != other.container.area() {
+            self.container.area() < other.container.area()
+        } else {
+            self.duration < other.duration
+        }
+    }
+
+    /// Describes how this evaluation was computed, one labeled line per quantity with the
+    /// arithmetic shown.
+    pub fn explain(&self) -> String {
+        let area = self.container.area();
+        format!(
+            "total rectangle area: {min}\n\
+             container: {w} x {h} = {area}\n\
+             empty area: {area} - {min} = {empty}\n\
+             filling rate: {min} / {area} = {rate:.3}",
+            min = self.min_area,
+            w = self.container.width,
+            h = self.container.height,
+            area = area,
+            empty = self.empty_area,
+            rate = self.filling_rate,
+        )
+    }
+
+    /// The competitive ratio of this evaluation: its container area divided by the
+    /// `optimal_area` found by a reference solver.
+    pub fn ratio_to(&self, optimal_area: u64) -> f64 {
+        self.container.area() as f64 / optimal_area as f64
+    }
+
+    /// Compares this evaluation to `baseline`, where `Greater` means this one is better.
+    ///
+    /// Prefers the smaller container, then the higher filling rate, then the shorter duration.
+    pub fn compare(&self, baseline: &Evaluation) -> Ordering {
+        baseline
+            .container
+            .area()
+            .cmp(&self.container.area())
+            .then(
+                self.filling_rate
+                    .partial_cmp(&baseline.filling_rate)
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then(baseline.duration.cmp(&self.duration))
+    }
+
+    /// Describes how this evaluation differs from `baseline`, e.g.
+    /// `area -3 (-1.2%), duration +0.15s`.
+    pub fn delta_report(&self, baseline: &Evaluation) -> String {
+        let (area, base_area) = (self.container.area(), baseline.container.area());
+        let delta = area as i64 - base_area as i64;
+        let percentage = if base_area == 0 {
+            0.
+        } else {
+            delta as f64 / base_area as f64 * 100.
+        };
+
+        format!(
+            "area {:+} ({:+.1}%), duration {:+.2}s",
+            delta,
+            percentage,
+            secs(self.duration) - secs(baseline.duration)
+        )
+    }
+}
+
+/// The length of `d` in seconds, with fractions.
+pub fn secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + f64::from(d.subsec_nanos()) * 1e-9
 }
 
 impl fmt::Display for Evaluation {
@@ -110,28 +1173,123 @@ impl fmt::Display for Evaluation {
             empty_area,
             filling_rate,
             duration,
+            total_overlap_area,
+            out_of_bounds_count,
+            unplaced_count,
+            height_utilization,
+            ..
         } = self;
         let bb_area = container.area();
 
         write!(
             f,
             "lower bound on area: {}\nbounding box: {}, area: {}\nunused area in bounding box: \
-             {}\nfilling_rate: {:.2}\ntook {}.{:.3}s",
+             {}\nfilling_rate: {:.2}\nheight utilization: {:.2}\ntook {}.{:.3}s",
             min_area,
             container,
             bb_area,
             empty_area,
             filling_rate,
+            height_utilization,
             duration.as_secs(),
             duration.subsec_millis(),
-        )
+        )?;
+
+        if *total_overlap_area > 0 {
+            write!(f, "\noverlapping area: {}", total_overlap_area)?;
+        }
+        if *out_of_bounds_count > 0 {
+            write!(f, "\nplacements out of bounds: {}", out_of_bounds_count)?;
+        }
+        if *unplaced_count > 0 {
+            write!(f, "\nunplaced rectangles: {}", unplaced_count)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Solutions are equal if they place the same rectangles in the same positions for the same
+/// problem. Whether partial solutions are accepted, whether the fragmentation is measured, the
+/// running time reported by the solver and the cached evaluation are ignored.
+impl PartialEq for Solution {
+    fn eq(&self, other: &Solution) -> bool {
+        self.variant == other.variant
+            && self.allow_rotation == other.allow_rotation
+            && self.unplaced == other.unplaced
+            && self.source == other.source
+            && self.placements == other.placements
+    }
+}
+
+impl FromStr for Solution {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        Solution::from_str_with(s, Origin::BottomLeft)
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonSolution {
+    placements: Vec<Option<JsonPlacement>>,
+}
+
+#[derive(Deserialize)]
+struct JsonPlacement {
+    x: u32,
+    y: u32,
+    #[serde(default)]
+    rotated: bool,
+}
+
+/// Where the origin of the coordinates in solver output lies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Origin {
+    /// The default: `x y` is the bottom-left corner of a rectangle, with y growing upward.
+    BottomLeft,
+    /// `x y` is the top-left corner of a rectangle, with y growing downward from the top of
+    /// the container. Only possible for problems with a fixed height.
+    TopLeft,
+}
+
+impl Solution {
+    /// Reads a solution in the usual text format, including the problem, from `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Solution> {
+        let mut content = String::new();
+        File::open(path)?.read_to_string(&mut content)?;
+        content.parse()
     }
-}
 
-impl FromStr for Solution {
-    type Err = Error;
+    /// Parses a solution from solver output that may contain other lines, such as log
+    /// messages, before the problem.
+    ///
+    /// Everything before the `container height:` line, or the `name:` line right before it,
+    /// is ignored. Lines after the last placement are ignored as well.
+    pub fn extract_from(output: &str) -> Result<Solution> {
+        let lines: Vec<&str> = output.lines().collect();
+        let header = lines
+            .iter()
+            .position(|l| l.trim().starts_with("container height:"))
+            .ok_or_else(|| format_err!("Solver output does not contain a problem"))?;
 
-    fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        let start = match header.checked_sub(1) {
+            Some(i) if lines[i].trim().starts_with("name:") => i,
+            _ => header,
+        };
+
+        lines[start..].join("\n").parse()
+    }
+
+    /// Parses a solution whose coordinates are relative to `origin`, converting them to the
+    /// usual bottom-left coordinates.
+    pub fn from_str_with(s: &str, origin: Origin) -> Result<Solution> {
+        Solution::from_str_with_unplaced(s, origin, DEFAULT_UNPLACED)
+    }
+
+    /// Like `from_str_with`, but a placement line equal to one of `sentinels`, ignoring
+    /// comments and repeated whitespace, marks its rectangle as not placed.
+    pub fn from_str_with_unplaced(s: &str, origin: Origin, sentinels: &[&str]) -> Result<Solution> {
         let mut parts = s.split("placement of rectangles").map(str::trim);
 
         let problem: Problem = parts
@@ -142,42 +1300,74 @@ impl FromStr for Solution {
         let Problem {
             variant,
             allow_rotation,
-            source,
             rectangles,
+            ..
         } = problem;
 
-        let n = rectangles.len();
-        let placements: Vec<Placement> = parts
+        let height = match (origin, variant.fixed_height()) {
+            (Origin::TopLeft, None) => {
+                bail!("A top-left origin requires a problem with a fixed height")
+            }
+            (Origin::TopLeft, height) => height,
+            (Origin::BottomLeft, _) => None,
+        };
+
+        let is_unplaced = |line: &str| is_sentinel(line, sentinels);
+
+        let lines: Vec<&str> = parts
             .next()
             .ok_or_else(|| format_err!("Unexpected end of file: unable to parse placements"))?
             .lines()
-            .map(|s| {
-                let tokens: Vec<&str> = s.split_whitespace().collect();
-                let result = match (allow_rotation, tokens.as_slice()) {
-                    (false, [x, y]) => {
-                        let p = Point::new(x.parse()?, y.parse()?);
-                        (Normal, p)
-                    }
-                    (true, [rot, x, y]) => {
-                        let p = Point::new(x.parse()?, y.parse()?);
-                        (rot.parse()?, p)
-                    }
-                    _ => bail!("Invalid format: {}", tokens.join(" ")),
-                };
+            .collect();
+        if lines.len() < rectangles.len() {
+            bail!("Solution contains a different number of placements than rectangles");
+        }
 
-                Ok(result)
-            })
+        let unplaced: Vec<(usize, Rectangle)> = lines
+            .iter()
             .zip(rectangles.iter())
-            .map(|(result, &r)| result.map(|(rot, coord)| Placement::new(r, rot, coord)))
-            .collect::<Result<_, _>>()?;
+            .enumerate()
+            .filter(|(_, (line, _))| is_unplaced(line))
+            .map(|(i, (_, &r))| (i, r))
+            .collect();
 
-        if placements.len() != n {
-            bail!("Solution contains a different number of placements than rectangles");
-        }
+        let placements: Vec<Placement> = lines
+            .iter()
+            .zip(rectangles.iter())
+            .enumerate()
+            .filter(|(_, (line, _))| !is_unplaced(line))
+            .map(|(i, (s, &r))| {
+                let (rot, mut coord) = parse_placement(allow_rotation, s)
+                    .map_err(|e| format_err!("{} on placement line {}", e, i + 1))?;
+                if let Some(k) = height {
+                    let h = match rot {
+                        Normal => r.height,
+                        Rotated => r.width,
+                    };
+
+                    let top = coord.y.checked_add(h);
+                    coord.y = top.and_then(|top| k.checked_sub(top)).ok_or_else(|| {
+                        format_err!(
+                            "Placement at {} {} exceeds the top of the container",
+                            coord.x,
+                            coord.y
+                        )
+                    })?;
+                }
+
+                Ok(Placement::new(r, rot, coord))
+            })
+            .collect::<Result<_, Error>>()?;
 
         Ok(Solution {
             variant,
             allow_rotation,
+            partial: false,
+            with_fragmentation: false,
+            tight: false,
+            reported_duration: None,
+            evaluation: None,
+            unplaced,
             source: None,
             placements,
         })
@@ -188,24 +1378,61 @@ impl FromStr for Solution {
 mod tests {
 
     use super::*;
-    use domain::{problem::Variant, Rectangle};
+    use problem::Variant;
+    use std::env;
+    use std::io::Write;
     use std::iter;
 
+    /// A solution without a source problem in which every rectangle is placed.
+    fn solution(variant: Variant, placements: Vec<Placement>) -> Solution {
+        Solution {
+            variant,
+            allow_rotation: false,
+            partial: false,
+            with_fragmentation: false,
+            tight: false,
+            reported_duration: None,
+            evaluation: None,
+            unplaced: Vec::new(),
+            source: None,
+            placements,
+        }
+    }
+
+    /// A 10x10 container with a filling rate of 0.8, found in 2 seconds.
+    fn sample_evaluation() -> Evaluation {
+        Evaluation {
+            container: Rectangle::new(10, 10),
+            occupied: Rectangle::new(10, 10),
+            min_area: 80,
+            empty_area: 20,
+            filling_rate: 0.8,
+            duration: Duration::from_secs(2),
+            total_overlap_area: 0,
+            out_of_bounds_count: 0,
+            unplaced_count: 0,
+            height_utilization: 1.0,
+            container_perimeter: 40,
+            rotations_used: 0,
+            fragmentation: None,
+            efficiency: None,
+            phases: None,
+            solver_reported_duration: None,
+        }
+    }
+
     #[test]
     fn solution_parsing() {
         let r1 = Rectangle::new(12, 8);
         let r2 = Rectangle::new(10, 9);
 
-        let expected = Solution {
-            variant: Variant::Fixed(22),
-            allow_rotation: false,
-            source: None,
-            evaluation: None,
-            placements: vec![
+        let expected = solution(
+            Variant::Fixed(22),
+            vec![
                 Placement::new(r1, Normal, Point::new(0, 0)),
                 Placement::new(r2, Normal, Point::new(24, 3)),
             ],
-        };
+        );
 
         let input = "container height: fixed 22\nrotations allowed: no\nnumber of rectangles: \
                      6\n12 8\n10 9\nplacement of rectangles\n0 0\n24 3";
@@ -214,6 +1441,344 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn from_path() {
+        let input = "container height: fixed 22\nrotations allowed: no\nnumber of rectangles: \
+                     2\n12 8\n10 9\nplacement of rectangles\n0 0\n12 0";
+        let path = env::temp_dir().join("packt_solution_from_path.txt");
+        File::create(&path)
+            .and_then(|mut f| f.write_all(input.as_bytes()))
+            .unwrap();
+
+        assert_eq!(
+            Solution::from_path(&path).unwrap(),
+            input.parse::<Solution>().unwrap()
+        );
+        assert!(Solution::from_path(path.with_extension("missing")).is_err());
+    }
+
+    #[test]
+    fn extract_from_noisy_output() {
+        let input = "name: two\ncontainer height: fixed 22\nrotations allowed: no\nnumber of \
+                     rectangles: 2\n12 8\n10 9\nplacement of rectangles\n0 0\n12 0";
+        let noisy = format!(
+            "Loading solver...\n[INFO] using 4 threads\n12 8\n{}\nDone in 0.3s",
+            input
+        );
+
+        assert!(noisy.parse::<Solution>().is_err());
+        assert_eq!(
+            Solution::extract_from(&noisy).unwrap(),
+            input.parse::<Solution>().unwrap()
+        );
+        assert!(Solution::extract_from("no solution here").is_err());
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                       2\n12 8\n10 9\nplacement of rectangles\n";
+
+        let negative = format!("{}0 0\n-1 0", problem);
+        let error = negative.parse::<Solution>().unwrap_err().to_string();
+        assert_eq!(error, "Invalid coordinate '-1' on placement line 2");
+
+        let text = format!("{}0 zero\n12 0", problem);
+        let error = text.parse::<Solution>().unwrap_err().to_string();
+        assert_eq!(error, "Invalid coordinate 'zero' on placement line 1");
+    }
+
+    #[test]
+    fn mixed_rotation_tokens() {
+        let input = "container height: free\nrotations allowed: yes\nnumber of rectangles: \
+                     2\n12 8\n10 9\nplacement of rectangles\n0 0\nyes 12 0";
+
+        let mut result: Solution = input.parse().unwrap();
+        assert_eq!(
+            result.placements,
+            vec![
+                Placement::new(Rectangle::new(12, 8), Normal, Point::new(0, 0)),
+                Placement::new(Rectangle::new(10, 9), Rotated, Point::new(12, 0)),
+            ]
+        );
+
+        assert_eq!(result.rotated_count(), 1);
+        let evaluation = result.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.rotations_used, 1);
+    }
+
+    #[test]
+    fn annotated_round_trip() {
+        let input = "container height: free\nrotations allowed: yes\nnumber of rectangles: \
+                     2\n12 8\n10 9\nplacement of rectangles\n0 0\nyes 12 0";
+        let solution: Solution = input.parse().unwrap();
+
+        let annotated = solution.to_string_annotated();
+        assert!(annotated.ends_with("no 0 0  # rect 0: 12 8\nyes 12 0  # rect 1: 10 9"));
+        assert_eq!(annotated.parse::<Solution>().unwrap(), solution);
+    }
+
+    #[test]
+    fn placements_only() {
+        let problem = "container height: free\nrotations allowed: yes\nnumber of rectangles: \
+                       3\n12 8\n10 9\n3 3";
+        let input = format!("{}\nplacement of rectangles\n0 0\nyes 12 0\nskip", problem);
+        let solution: Solution = input.parse().unwrap();
+
+        let placements = solution.to_placements_only();
+        assert_eq!(placements, "placement of rectangles\nno 0 0\nyes 12 0\n- -");
+        assert!(!placements.contains("container height:"));
+
+        let joined = format!("{}\n{}", problem, placements);
+        assert_eq!(joined.parse::<Solution>().unwrap(), solution);
+    }
+
+    #[test]
+    fn origins() {
+        let input = "container height: fixed 10\nrotations allowed: yes\nnumber of rectangles: \
+                     2\n4 3\n2 5\nplacement of rectangles\n0 0\nyes 4 2";
+
+        let bottom_left = Solution::from_str_with(input, Origin::BottomLeft).unwrap();
+        assert_eq!(bottom_left, input.parse().unwrap());
+        assert_eq!(bottom_left.placements[0].bottom_left, Point::new(0, 0));
+        assert_eq!(bottom_left.placements[1].bottom_left, Point::new(4, 2));
+
+        let top_left = Solution::from_str_with(input, Origin::TopLeft).unwrap();
+        assert_eq!(top_left.placements[0].bottom_left, Point::new(0, 7));
+        assert_eq!(top_left.placements[1].bottom_left, Point::new(4, 6));
+
+        let free = input.replace("fixed 10", "free");
+        assert!(Solution::from_str_with(&free, Origin::TopLeft).is_err());
+
+        let overflow = input.replace("\n0 0\n", "\n0 4294967295\n");
+        let error = Solution::from_str_with(&overflow, Origin::TopLeft).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("exceeds the top of the container"));
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let r = Rectangle::new(4, 6);
+        let mut solution = solution(
+            Variant::Fixed(10),
+            vec![
+                Placement::new(r, Normal, Point::new(0, 0)),
+                Placement::new(r, Normal, Point::new(4, 5)),
+                Placement::new(r, Normal, Point::new(8, 4)),
+                Placement::new(r, Normal, Point::new(12, 7)),
+            ],
+        );
+
+        assert_eq!(solution.out_of_bounds(), vec![(1, 1), (3, 3)]);
+        let error = solution.container().unwrap_err().to_string();
+        assert!(error.contains("placement 1 by 1, placement 3 by 3"));
+
+        assert!(solution.evaluate(Duration::from_secs(1)).is_err());
+        let evaluation = solution.evaluate_lenient(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.out_of_bounds_count, 2);
+        assert_eq!(evaluation.container, Rectangle::new(16, 10));
+    }
+
+    #[test]
+    fn occupied_bounds() {
+        let r = Rectangle::new(4, 3);
+        let mut solution = solution(
+            Variant::Fixed(10),
+            vec![
+                Placement::new(r, Normal, Point::new(0, 0)),
+                Placement::new(r, Normal, Point::new(4, 3)),
+            ],
+        );
+
+        assert_eq!(solution.occupied_bounds(), Rectangle::new(8, 6));
+        let evaluation = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.container, Rectangle::new(8, 10));
+        assert_eq!(evaluation.occupied, Rectangle::new(8, 6));
+
+        solution.placements.clear();
+        assert_eq!(solution.occupied_bounds(), Rectangle::new(0, 0));
+    }
+
+    #[test]
+    fn height_utilization() {
+        let short = "container height: fixed 10\nrotations allowed: no\nnumber of rectangles: \
+                     2\n4 3\n4 3\nplacement of rectangles\n0 0\n4 1";
+        let mut solution: Solution = short.parse().unwrap();
+        let evaluation = solution.evaluate(Duration::from_secs(0)).unwrap();
+        assert_eq!(evaluation.height_utilization, 0.4);
+        assert!(evaluation.to_string().contains("height utilization: 0.40"));
+
+        let full = short.replace("4 1", "0 7");
+        let mut solution: Solution = full.parse().unwrap();
+        let evaluation = solution.evaluate(Duration::from_secs(0)).unwrap();
+        assert_eq!(evaluation.height_utilization, 1.0);
+    }
+
+    #[test]
+    fn json_solution() {
+        let text = "container height: free\nrotations allowed: yes\nnumber of rectangles: \
+                    2\n12 8\n10 9\nplacement of rectangles\n0 0\nyes 12 0";
+        let json = r#"{"placements": [{"x": 0, "y": 0}, {"x": 12, "y": 0, "rotated": true}]}"#;
+
+        let mut expected: Solution = text.parse().unwrap();
+        let problem: Problem = text.split("placement").next().unwrap().parse().unwrap();
+        expected.source(problem.clone());
+        assert_eq!(Solution::from_json(&problem, json).unwrap(), expected);
+
+        let too_short = r#"{"placements": [{"x": 0, "y": 0}]}"#;
+        assert!(Solution::from_json(&problem, too_short).is_err());
+
+        let partial = r#"{"placements": [null, {"x": 12, "y": 0}]}"#;
+        let solution = Solution::from_json(&problem, partial).unwrap();
+        assert_eq!(solution.unplaced_count(), 1);
+        assert!(solution.placement_slots()[0].is_none());
+        assert_eq!(solution.placement_count(), 1);
+    }
+
+    #[test]
+    fn occupancy_grid() {
+        let solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(Rectangle::new(4, 2), Normal, Point::new(0, 0)),
+                Placement::new(Rectangle::new(2, 2), Normal, Point::new(3, 1)),
+            ],
+        );
+
+        assert_eq!(
+            solution.occupancy_grid(1),
+            vec![
+                vec![1, 1, 1, 1, 0],
+                vec![1, 1, 1, 2, 1],
+                vec![0, 0, 0, 1, 1],
+            ]
+        );
+        assert_eq!(
+            solution.occupancy_grid(2),
+            vec![vec![1, 2, 1], vec![0, 1, 1]]
+        );
+    }
+
+    #[test]
+    fn efficiency() {
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: 2\n10 \
+                       5\n10 5";
+        let mut solution: Solution = format!("{}\nplacement of rectangles\n0 0\n0 5", problem)
+            .parse()
+            .unwrap();
+        assert_eq!(solution.efficiency(), None);
+
+        let mut problem: Problem = problem.parse().unwrap();
+        problem.source = Some(Rectangle::new(10, 10));
+        solution.source(problem);
+        assert_eq!(solution.efficiency(), Some(1.));
+
+        let evaluation = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.efficiency, Some(1.));
+
+        solution.placements[1] = Placement::new(Rectangle::new(10, 5), Normal, Point::new(0, 10));
+        assert_eq!(solution.efficiency(), Some(100. / 150.));
+    }
+
+    #[test]
+    fn evaluation_cache() {
+        let mut solution: Solution = "container height: free\nrotations allowed: no\nnumber of \
+                                      rectangles: 2\n10 5\n10 5\nplacement of rectangles\n0 \
+                                      0\n0 5"
+            .parse()
+            .unwrap();
+
+        let unevaluated = solution.clone();
+        let first = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(solution.evaluation, Some((0, first)));
+        assert_eq!(solution, unevaluated);
+        let second = solution.evaluate(Duration::from_secs(2)).unwrap();
+        assert_eq!(
+            second,
+            Evaluation {
+                duration: Duration::from_secs(2),
+                ..first
+            }
+        );
+
+        solution
+            .try_place(Placement::new(
+                Rectangle::new(10, 5),
+                Normal,
+                Point::new(0, 10),
+            ))
+            .unwrap();
+        assert_eq!(solution.evaluation, None);
+        let third = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(third.container, Rectangle::new(10, 15));
+        assert_ne!(third, first);
+    }
+
+    #[test]
+    fn integral_grid() {
+        let mut solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(Rectangle::new(4, 2), Normal, Point::new(0, 0)),
+                Placement::new(Rectangle::new(4, 2), Rotated, Point::new(4, 0)),
+            ],
+        );
+        assert!(solution.assert_integral_grid().is_ok());
+
+        solution.placements[1].top_right = Point::new(9, 1);
+        assert!(solution.assert_integral_grid().is_err());
+
+        solution.placements[1] = Placement {
+            rectangle: Rectangle::new(0, 2),
+            rotation: Normal,
+            bottom_left: Point::new(4, 0),
+            top_right: Point::new(4, 1),
+        };
+        let error = solution.assert_integral_grid().unwrap_err().to_string();
+        assert_eq!(error, "Placement 1 has a zero dimension: 0 by 2");
+        assert!(solution.evaluate(Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn fragmentation() {
+        let r = Rectangle::new(1, 1);
+        let mut solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(r, Normal, Point::new(0, 0)),
+                Placement::new(r, Normal, Point::new(2, 1)),
+            ],
+        );
+
+        // The empty cells form an S: two horizontal pairs and the column connecting them
+        assert_eq!(solution.fragmentation().unwrap(), 3);
+
+        solution.placements = vec![Placement::new(
+            Rectangle::new(3, 2),
+            Normal,
+            Point::new(0, 0),
+        )];
+        assert_eq!(solution.fragmentation().unwrap(), 0);
+
+        solution.variant = Variant::Fixed(4);
+        assert_eq!(solution.fragmentation().unwrap(), 1);
+
+        let evaluation = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.fragmentation, None);
+        solution.measure_fragmentation();
+        let evaluation = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.fragmentation, Some(1));
+
+        solution.variant = Variant::Fixed(100_000);
+        solution.placements = vec![Placement::new(
+            Rectangle::new(101, 1),
+            Normal,
+            Point::new(0, 0),
+        )];
+        assert!(solution.fragmentation().is_err());
+    }
+
     #[test]
     fn validation() {
         let r = Rectangle::new(10, 9);
@@ -228,15 +1793,7 @@ mod tests {
             })
             .collect();
 
-        let mut solution = {
-            Solution {
-                variant: Variant::Fixed(22),
-                allow_rotation: false,
-                source: None,
-                evaluation: None,
-                placements,
-            }
-        };
+        let mut solution = solution(Variant::Fixed(22), placements);
 
         assert!(solution.is_valid());
         let p = Placement::new(r, Normal, Point::new(0, 0));
@@ -245,4 +1802,353 @@ mod tests {
         assert!(!solution.is_valid());
     }
 
+    #[test]
+    fn margin_validation() {
+        let r = Rectangle::new(10, 9);
+        let mut solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(r, Normal, Point::new(0, 0)),
+                Placement::new(r, Normal, Point::new(12, 0)),
+            ],
+        );
+
+        assert!(solution.is_valid());
+        assert!(solution.is_valid_with_margin(2));
+        assert!(!solution.is_valid_with_margin(3));
+        assert!(solution
+            .evaluate_with_margin(Duration::from_secs(1), 3)
+            .is_err());
+    }
+
+    #[test]
+    fn tighten() {
+        let r = Rectangle::new(10, 9);
+        let mut solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(r, Normal, Point::new(5, 3)),
+                Placement::new(r, Normal, Point::new(15, 3)),
+            ],
+        );
+
+        let loose = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(loose.container, Rectangle::new(25, 12));
+
+        solution.tighten();
+        let tight = solution.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(tight.container, Rectangle::new(20, 9));
+        assert!(tight.filling_rate > loose.filling_rate);
+        assert_eq!(tight.filling_rate, 1.);
+        assert_eq!(solution.container_origin(), Point::new(5, 3));
+        assert_eq!(solution.placements[1].bottom_left, Point::new(15, 3));
+        assert_eq!(solution.fragmentation().unwrap(), 0);
+        assert_eq!(solution.exposed_perimeter(), 0);
+        assert_eq!(solution.occupancy_grid(10), vec![vec![1, 1]]);
+
+        solution.variant = Variant::Fixed(12);
+        assert_eq!(solution.container_origin(), Point::new(5, 0));
+        assert_eq!(solution.container().unwrap(), Rectangle::new(20, 12));
+        assert_eq!(solution.fragmentation().unwrap(), 1);
+    }
+
+    #[test]
+    fn canonical_form() {
+        let input = "container height: fixed 10\nrotations allowed: no\nnumber of rectangles: \
+                     3\n4 3\n2 2\n6 1\nplacement of rectangles\n0 0\n4 0\n0 3";
+        let solution: Solution = input.parse().unwrap();
+        let canonical = solution.canonical_form();
+        assert_eq!(canonical.canonical_form(), canonical);
+
+        let translated: Solution = input
+            .replace("0 0\n4 0\n0 3", "3 5\n7 5\n3 8")
+            .parse()
+            .unwrap();
+        assert_ne!(translated, solution);
+        assert_eq!(translated.canonical_form(), canonical);
+
+        // Mirrored in the vertical axis of the 6x4 bounding box
+        let mirrored: Solution = input
+            .replace("0 0\n4 0\n0 3", "2 0\n0 0\n0 3")
+            .parse()
+            .unwrap();
+        assert_eq!(mirrored.canonical_form(), canonical);
+    }
+
+    #[test]
+    fn tolerance_validation() {
+        let r = Rectangle::new(10, 9);
+        let solution = solution(
+            Variant::Free,
+            vec![
+                Placement::new(r, Normal, Point::new(0, 0)),
+                Placement::new(r, Normal, Point::new(9, 0)),
+            ],
+        );
+
+        assert!(!solution.is_valid());
+        assert!(!solution.is_valid_with_tolerance(0));
+        assert!(solution.is_valid_with_tolerance(1));
+    }
+
+    #[test]
+    fn streaming_validation() {
+        let problem: Problem = "container height: free\nrotations allowed: no\nnumber of \
+                                rectangles: 3\n10 10\n10 10\n10 10"
+            .parse()
+            .unwrap();
+
+        let valid = "placement of rectangles\n0 0\n10 0\n20 0\n";
+        let solution = Solution::from_stream(problem.clone(), valid.as_bytes()).unwrap();
+        assert!(solution.is_valid());
+
+        let partial = "placement of rectangles\n0 0\n- -\n20 0\n";
+        let solution = Solution::from_stream(problem.clone(), partial.as_bytes()).unwrap();
+        assert_eq!(solution.unplaced_count(), 1);
+        assert!(solution.placement_slots()[1].is_none());
+
+        let invalid = "placement of rectangles\n0 0\n5 5\n20 0\n";
+        let err = Solution::from_stream(problem, invalid.as_bytes()).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"));
+    }
+
+    #[test]
+    fn rotate_improvements() {
+        let input = "container height: free\nrotations allowed: yes\nnumber of rectangles: \
+                     2\n10 2\n10 2\nplacement of rectangles\nno 0 0\nyes 10 0";
+
+        let mut solution: Solution = input.parse().unwrap();
+        assert_eq!(solution.container().unwrap().area(), 12 * 10);
+        assert_eq!(solution.try_rotate_improvements(), 1);
+        assert!(solution.is_valid());
+        assert_eq!(solution.container().unwrap(), Rectangle::new(20, 2));
+    }
+
+    #[test]
+    fn threshold() {
+        let evaluation = Evaluation {
+            duration: Duration::from_secs(1),
+            ..sample_evaluation()
+        };
+
+        assert!(evaluation.meets_threshold(0.75));
+        assert!(evaluation.meets_threshold(0.8));
+        assert!(!evaluation.meets_threshold(0.9));
+    }
+
+    #[test]
+    fn comparison() {
+        let base = sample_evaluation();
+
+        let denser = Evaluation {
+            filling_rate: 0.9,
+            duration: Duration::from_secs(10),
+            ..base
+        };
+        let smaller = Evaluation {
+            container: Rectangle::new(10, 9),
+            ..base
+        };
+        let faster = Evaluation {
+            duration: Duration::from_secs(1),
+            ..base
+        };
+
+        assert!(denser.better_than(&base));
+        assert!(smaller.better_than(&base));
+        assert!(faster.better_than(&base));
+        assert!(denser.better_than(&faster));
+        assert!(!base.better_than(&base));
+    }
+
+    #[test]
+    fn explain() {
+        let explanation = sample_evaluation().explain();
+
+        assert!(explanation.contains("total rectangle area: 80"));
+        assert!(explanation.contains("container: 10 x 10 = 100"));
+        assert!(explanation.contains("empty area: 100 - 80 = 20"));
+        assert!(explanation.contains("filling rate: 80 / 100 = 0.800"));
+    }
+
+    #[test]
+    fn ratio_to_optimal() {
+        let evaluation = sample_evaluation();
+        assert_eq!(evaluation.ratio_to(100), 1.);
+        assert_eq!(evaluation.ratio_to(80), 1.25);
+    }
+
+    #[test]
+    fn compare_to_baseline() {
+        let base = sample_evaluation();
+
+        let improvement = Evaluation {
+            container: Rectangle::new(10, 9),
+            duration: Duration::from_millis(2150),
+            ..base
+        };
+        assert_eq!(improvement.compare(&base), Ordering::Greater);
+        assert_eq!(
+            improvement.delta_report(&base),
+            "area -10 (-10.0%), duration +0.15s"
+        );
+
+        let regression = Evaluation {
+            filling_rate: 0.7,
+            ..base
+        };
+        assert_eq!(regression.compare(&base), Ordering::Less);
+
+        assert_eq!(base.compare(&base), Ordering::Equal);
+        assert_eq!(base.delta_report(&base), "area +0 (+0.0%), duration +0.00s");
+    }
+
+    #[test]
+    fn rotated_container_bounds() {
+        let problem = "container height: fixed 10\nrotations allowed: yes\nnumber of \
+                       rectangles: 2\n3 8\n2 4\nplacement of rectangles";
+
+        let solution: Solution = format!("{}\nno 0 0\nyes 3 8", problem).parse().unwrap();
+        assert_eq!(solution.container().unwrap(), Rectangle::new(7, 10));
+
+        let solution: Solution = format!("{}\nno 0 0\nno 3 6", problem).parse().unwrap();
+        assert_eq!(solution.container().unwrap(), Rectangle::new(5, 10));
+
+        let solution: Solution = format!("{}\nno 0 0\nyes 3 9", problem).parse().unwrap();
+        assert!(solution.container().is_err());
+
+        let free = problem.replace("fixed 10", "free");
+        let solution: Solution = format!("{}\nno 0 0\nyes 6 8", free).parse().unwrap();
+        assert_eq!(solution.container().unwrap(), Rectangle::new(10, 10));
+    }
+
+    #[test]
+    fn accessors() {
+        let input = "container height: fixed 22\nrotations allowed: yes\nnumber of rectangles: \
+                     1\n12 8\nplacement of rectangles\nno 0 0";
+
+        let solution: Solution = input.parse().unwrap();
+        assert_eq!(solution.variant(), Variant::Fixed(22));
+        assert!(solution.allow_rotation());
+    }
+
+    #[test]
+    fn lenient_evaluation() {
+        let input = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                     2\n5 5\n5 5\nplacement of rectangles\n0 0\n3 3";
+
+        let mut solution: Solution = input.parse().unwrap();
+        assert!(solution.evaluate(Duration::from_secs(0)).is_err());
+
+        let evaluation = solution.evaluate_lenient(Duration::from_secs(0)).unwrap();
+        assert_eq!(evaluation.total_overlap_area, 4);
+        assert_eq!(evaluation.container, Rectangle::new(8, 8));
+    }
+
+    #[test]
+    fn unplaced_rectangles() {
+        let input = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                     3\n5 5\n4 4\n3 3\nplacement of rectangles\n0 0\n-  -  # gave up\n5 0";
+
+        let mut solution: Solution = input.parse().unwrap();
+        assert_eq!(solution.placement_count(), 2);
+        assert_eq!(solution.unplaced_count(), 1);
+        assert!(solution.placement_slots()[1].is_none());
+        assert!(solution
+            .to_string_annotated()
+            .contains("\n- -  # rect 1: 4 4"));
+        assert!(solution.evaluate(Duration::from_secs(0)).is_err());
+
+        solution.accept_partial();
+        let evaluation = solution.evaluate(Duration::from_secs(0)).unwrap();
+        assert_eq!(evaluation.min_area, 34);
+        assert_eq!(evaluation.unplaced_count, 1);
+
+        let skip = input.replace("-  -  # gave up", "skip");
+        let custom = skip.replace("skip", "none");
+        assert_eq!(skip.parse::<Solution>().unwrap().unplaced_count(), 1);
+        assert!(Solution::from_str_with(&custom, Origin::BottomLeft).is_err());
+        assert_eq!(
+            Solution::from_str_with_unplaced(&custom, Origin::BottomLeft, &["none"])
+                .unwrap()
+                .unplaced_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn weighted_score() {
+        let evaluation = Evaluation {
+            filling_rate: 0.75,
+            duration: Duration::from_millis(2500),
+            ..sample_evaluation()
+        };
+
+        assert_eq!(evaluation.weighted_score(0.), 0.75);
+        assert!((evaluation.weighted_score(0.1) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_solution() {
+        let problem: Problem = "container height: free\nrotations allowed: no\nnumber of \
+                                rectangles: 1\n2 3"
+            .parse()
+            .unwrap();
+
+        let mut solution = Solution::empty(problem);
+        assert_eq!(solution.placement_count(), 0);
+
+        let err = solution.evaluate(Duration::from_secs(0)).unwrap_err();
+        assert_eq!(err.to_string(), "Solution contains no placements");
+    }
+
+    #[test]
+    fn connected_components() {
+        let input = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                     5\n2 2\n2 2\n2 2\n2 2\n2 2\nplacement of rectangles\n0 0\n10 10\n2 \
+                     0\n12 10\n4 1";
+
+        let solution: Solution = input.parse().unwrap();
+        assert_eq!(
+            solution.connected_components(),
+            vec![vec![0, 2, 4], vec![1, 3]]
+        );
+    }
+
+    #[test]
+    fn adjacency_count() {
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                       2\n2 3\n2 3\nplacement of rectangles\n0 0\n";
+        let count = |second: &str| {
+            format!("{}{}", problem, second)
+                .parse::<Solution>()
+                .unwrap()
+                .adjacency_count()
+        };
+
+        assert_eq!(count("2 1"), 1);
+        assert_eq!(count("3 0"), 0);
+        assert_eq!(count("2 3"), 0);
+    }
+
+    #[test]
+    fn exposed_perimeter() {
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                       2\n2 3\n2 1\nplacement of rectangles\n0 0\n";
+        let parse = |second: &str| {
+            format!("{}{}", problem, second)
+                .parse::<Solution>()
+                .unwrap()
+        };
+
+        // A 4x3 container with an empty 2x2 square in the top right, bordered by the top of
+        // the second rectangle and the right side of the first
+        let mut l_shape = parse("2 0");
+        assert_eq!(l_shape.exposed_perimeter(), 4);
+        let evaluation = l_shape.evaluate(Duration::from_secs(1)).unwrap();
+        assert_eq!(evaluation.container_perimeter, 14);
+
+        // Stacked, the 2x1 rectangle closes off the 2x4 container
+        assert_eq!(parse("0 3").exposed_perimeter(), 0);
+    }
 }
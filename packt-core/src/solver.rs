@@ -0,0 +1,52 @@
+//! Solvers that run in-process, without spawning an external solver.
+
+use failure::Error;
+use problem::Problem;
+use solution::{Evaluation, Solution};
+use std::time::Instant;
+
+pub trait Solver {
+    fn solve(&self, problem: &Problem) -> Result<Solution, Error>;
+}
+
+/// The greedy bottom-left baseline, see `Problem::greedy_bottom_left`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedyBottomLeft;
+
+impl Solver for GreedyBottomLeft {
+    fn solve(&self, problem: &Problem) -> Result<Solution, Error> {
+        problem.greedy_bottom_left()
+    }
+}
+
+/// Solves `problem` with `solver` and evaluates the result, timing the solver the same way
+/// as an external one.
+pub fn solve_and_evaluate<S: Solver + ?Sized>(
+    solver: &S,
+    problem: Problem,
+) -> Result<Evaluation, Error> {
+    let start = Instant::now();
+    let mut solution = solver.solve(&problem)?;
+    let duration = start.elapsed();
+
+    solution.source(problem);
+    solution.evaluate(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greedy_through_trait() {
+        let problem: Problem = "container height: fixed 10\nrotations allowed: no\nnumber of \
+                                rectangles: 3\n5 10\n5 5\n5 5"
+            .parse()
+            .unwrap();
+
+        let solver: &dyn Solver = &GreedyBottomLeft;
+        let evaluation = solve_and_evaluate(solver, problem).unwrap();
+        assert_eq!(evaluation.min_area, 100);
+        assert_eq!(evaluation.filling_rate, 1.);
+    }
+}
@@ -0,0 +1,203 @@
+//! Helpers for drawing problems and solutions.
+
+use geometry::Rectangle;
+use solution::Solution;
+use std::iter;
+
+/// Options for drawing solutions, see `solution_svg`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VizOptions {
+    /// Color rectangles by their size using `size_color`, so repeated pieces stand out,
+    /// instead of by their index.
+    pub color_by_size: bool,
+    /// List the dimensions of every rectangle next to the drawing.
+    pub legend: bool,
+}
+
+impl VizOptions {
+    /// The color of the rectangle `r` at `index`.
+    pub fn color(&self, index: usize, r: &Rectangle) -> String {
+        if self.color_by_size {
+            size_color(r)
+        } else {
+            index_color(index)
+        }
+    }
+}
+
+/// Draws `solution` as an SVG image of its container, coloring rectangles as set in
+/// `options`.
+///
+/// By default every rectangle is colored by its index using `index_color`, so two solutions
+/// of the same problem can be compared side by side. If the solution has no valid container,
+/// e.g. because placements stick out above the fixed height, the area from the container
+/// origin that contains all placements is drawn instead.
+pub fn solution_svg(solution: &Solution, options: &VizOptions) -> String {
+    const SIZE: f64 = 500.;
+    const ROW: f64 = 16.;
+
+    let placements = solution.placements();
+    let origin = solution.container_origin();
+    let container = solution.container().unwrap_or_else(|_| {
+        let corners = placements.iter().map(|p| p.top_right);
+        Rectangle::bounding_of(corners.chain(iter::once(origin)))
+    });
+
+    let scale = SIZE / f64::from(container.width.max(container.height));
+    let (width, height) = (
+        f64::from(container.width) * scale,
+        f64::from(container.height) * scale,
+    );
+    let legend_x = width + 20.;
+    let (total_width, total_height) = if options.legend {
+        (
+            legend_x + 200.,
+            height.max(ROW * (placements.len() + 1) as f64),
+        )
+    } else {
+        (width, height)
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\">\n\
+         <rect x=\"0\" y=\"0\" width=\"{cw:.2}\" height=\"{ch:.2}\" fill=\"none\" \
+         stroke=\"black\"/>\n",
+        w = total_width,
+        h = total_height,
+        cw = width,
+        ch = height,
+    );
+
+    for (i, p) in placements.iter().enumerate() {
+        let color = options.color(i, &p.rectangle);
+        let (w, h) = (p.width(), p.height());
+        let x = f64::from(p.bottom_left.x - origin.x) * scale;
+        let y = height - f64::from(p.bottom_left.y - origin.y + h) * scale;
+
+        svg.push_str(&format!(
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\" \
+             stroke=\"black\" stroke-width=\"0.5\"/>\n",
+            x,
+            y,
+            f64::from(w) * scale,
+            f64::from(h) * scale,
+            color,
+        ));
+
+        if options.legend {
+            let row = ROW * (i + 1) as f64;
+            svg.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"10\" height=\"10\" fill=\"{}\"/>\n\
+                 <text x=\"{:.2}\" y=\"{:.2}\" font-size=\"12\">{}: {}</text>\n",
+                legend_x,
+                row - 10.,
+                color,
+                legend_x + 14.,
+                row,
+                i,
+                p.rectangle,
+            ));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A color for the rectangle at `index`, as a CSS color string.
+///
+/// Colors only depend on the index, so a rectangle is drawn in the same color in every
+/// solution of a problem. Consecutive indices are spread around the color wheel by the golden
+/// angle, so neighbouring rectangles are easy to tell apart.
+pub fn index_color(index: usize) -> String {
+    const GOLDEN_ANGLE: f64 = 137.507_764;
+
+    let hue = (index as f64 * GOLDEN_ANGLE) % 360.;
+    format!("hsl({:.1}, 65%, 55%)", hue)
+}
+
+/// A color for rectangles of the size of `r`, as a CSS color string.
+///
+/// The hue is taken from a hash of the width and height, so all rectangles of the same size
+/// share a color in every drawing. Rotated sizes, e.g. 2 by 3 and 3 by 2, are colored
+/// differently.
+pub fn size_color(r: &Rectangle) -> String {
+    // Mixes both dimensions with the 64-bit finalizer of MurmurHash3, which does not depend
+    // on the platform or compiler version, unlike the hasher of the standard library
+    let mut h = u64::from(r.width) << 32 | u64::from(r.height);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+
+    let hue = (h % 3600) as f64 / 10.;
+    format!("hsl({:.1}, 65%, 55%)", hue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_stability() {
+        assert_eq!(index_color(0), "hsl(0.0, 65%, 55%)");
+        assert_eq!(index_color(7), index_color(7));
+        assert_ne!(index_color(1), index_color(2));
+    }
+
+    #[test]
+    fn size_colors() {
+        let options = VizOptions {
+            color_by_size: true,
+            ..VizOptions::default()
+        };
+        let (small, large) = (Rectangle::new(2, 3), Rectangle::new(10, 4));
+
+        assert_eq!(options.color(0, &small), options.color(5, &small));
+        assert_ne!(options.color(0, &small), options.color(1, &large));
+        assert_ne!(size_color(&small), size_color(&Rectangle::new(3, 2)));
+        assert_eq!(VizOptions::default().color(3, &small), index_color(3));
+    }
+
+    #[test]
+    fn svg_colors() {
+        let problem = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                       2\n2 3\n4 1\nplacement of rectangles";
+
+        let first: Solution = format!("{}\n0 0\n2 0", problem).parse().unwrap();
+        let second: Solution = format!("{}\n4 0\n0 0", problem).parse().unwrap();
+        let fill = format!("fill=\"{}\"", index_color(1));
+        let options = VizOptions {
+            legend: true,
+            ..VizOptions::default()
+        };
+
+        for svg in &[
+            solution_svg(&first, &options),
+            solution_svg(&second, &options),
+        ] {
+            assert!(svg.starts_with("<svg"));
+            assert!(svg.contains(&fill));
+            assert!(svg.contains("1: 4 1</text>"));
+        }
+
+        let plain = solution_svg(&first, &VizOptions::default());
+        assert!(plain.contains(&fill));
+        assert!(!plain.contains("<text"));
+    }
+
+    #[test]
+    fn tightened_svg() {
+        let input = "container height: free\nrotations allowed: no\nnumber of rectangles: \
+                     2\n2 2\n2 2\nplacement of rectangles\n10 10\n12 10";
+        let mut solution: Solution = input.parse().unwrap();
+        solution.tighten();
+        let svg = solution_svg(&solution, &VizOptions::default());
+
+        let header = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"500\" height=\"250\">";
+        assert_eq!(svg.lines().next(), Some(header));
+        assert!(svg.contains("<rect x=\"0.00\" y=\"0.00\" width=\"250.00\" height=\"250.00\""));
+        assert!(svg.contains("<rect x=\"250.00\" y=\"0.00\" width=\"250.00\""));
+    }
+}
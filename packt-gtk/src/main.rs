@@ -7,16 +7,21 @@ extern crate relm;
 #[macro_use]
 extern crate relm_derive;
 extern crate crossbeam_channel;
+extern crate env_logger;
 #[macro_use]
 extern crate failure;
+#[macro_use]
+extern crate log;
 extern crate packt_core;
 extern crate tokio;
 extern crate tokio_core;
 extern crate tokio_io;
 extern crate tokio_process;
 
+mod recent;
 mod view;
 
 fn main() {
+    env_logger::init();
     relm::run::<view::Win>(()).unwrap();
 }
@@ -0,0 +1,178 @@
+//! The recently used problems and solvers, persisted across sessions in the user's config
+//! directory.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How many entries of every kind are kept.
+pub const MAX_RECENT: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Problem,
+    Solver,
+}
+
+impl Kind {
+    fn prefix(self) -> &'static str {
+        match self {
+            Kind::Problem => "problem ",
+            Kind::Solver => "solver ",
+        }
+    }
+}
+
+/// The recent files, most recent first.
+///
+/// Stored as one `problem <path>` or `solver <path>` line per entry in `packt/recent` under
+/// `$XDG_CONFIG_HOME`, `%APPDATA%` or `$HOME/.config`, whichever is found first.
+#[derive(Debug, Default)]
+pub struct Recent {
+    problems: Vec<PathBuf>,
+    solvers: Vec<PathBuf>,
+}
+
+impl Recent {
+    /// Reads the recent files, starting out empty if there are none yet or they cannot be
+    /// read.
+    pub fn load() -> Recent {
+        let content = match config_path().map(fs::read_to_string) {
+            Some(Ok(content)) => content,
+            Some(Err(ref e)) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Some(Err(e)) => {
+                warn!("Failed to read recent files: {}", e);
+                String::new()
+            }
+            None => String::new(),
+        };
+
+        let mut recent = Recent::default();
+        for line in content.lines() {
+            for &kind in &[Kind::Problem, Kind::Solver] {
+                if line.starts_with(kind.prefix()) {
+                    let path = PathBuf::from(&line[kind.prefix().len()..]);
+                    recent.entries_mut(kind).push(path);
+                }
+            }
+        }
+
+        recent
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory found"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut content = String::new();
+        for &kind in &[Kind::Problem, Kind::Solver] {
+            for entry in self.entries(kind) {
+                content.push_str(kind.prefix());
+                content.push_str(&entry.to_string_lossy());
+                content.push('\n');
+            }
+        }
+
+        fs::write(path, content)
+    }
+
+    pub fn entries(&self, kind: Kind) -> &[PathBuf] {
+        match kind {
+            Kind::Problem => &self.problems,
+            Kind::Solver => &self.solvers,
+        }
+    }
+
+    fn entries_mut(&mut self, kind: Kind) -> &mut Vec<PathBuf> {
+        match kind {
+            Kind::Problem => &mut self.problems,
+            Kind::Solver => &mut self.solvers,
+        }
+    }
+
+    /// The directories containing the recent files of `kind`, most recent first and without
+    /// duplicates. Suitable as file chooser shortcuts.
+    pub fn folders(&self, kind: Kind) -> Vec<PathBuf> {
+        let mut folders: Vec<PathBuf> = Vec::new();
+        for dir in self.entries(kind).iter().filter_map(|p| p.parent()) {
+            if !folders.iter().any(|f| f == dir) {
+                folders.push(dir.to_path_buf());
+            }
+        }
+
+        folders
+    }
+
+    /// Moves `path` to the front of the entries of `kind`, dropping the oldest entry if there
+    /// are more than `MAX_RECENT`.
+    pub fn push(&mut self, kind: Kind, path: &Path) {
+        let entries = self.entries_mut(kind);
+        entries.retain(|p| p != path);
+        entries.insert(0, path.to_path_buf());
+        entries.truncate(MAX_RECENT);
+    }
+}
+
+/// Adds `path` to the recent files of `kind` on disk. Failures are only logged, as the
+/// recent files are a convenience.
+pub fn remember(kind: Kind, path: &Path) {
+    let mut recent = Recent::load();
+    recent.push(kind, path);
+    if let Err(e) = recent.save() {
+        warn!("Failed to save recent files: {}", e);
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("packt").join("recent"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push() {
+        let mut recent = Recent::default();
+        for i in 0..MAX_RECENT + 2 {
+            recent.push(Kind::Problem, &PathBuf::from(format!("problems/{}.txt", i)));
+        }
+
+        let problems = recent.entries(Kind::Problem);
+        assert_eq!(problems.len(), MAX_RECENT);
+        assert_eq!(
+            problems[0],
+            PathBuf::from(format!("problems/{}.txt", MAX_RECENT + 1))
+        );
+        assert!(!problems.contains(&PathBuf::from("problems/0.txt")));
+        assert!(recent.entries(Kind::Solver).is_empty());
+
+        let again = PathBuf::from("problems/5.txt");
+        recent.push(Kind::Problem, &again);
+        let problems = recent.entries(Kind::Problem);
+        assert_eq!(problems.len(), MAX_RECENT);
+        assert_eq!(problems[0], again);
+        assert_eq!(problems.iter().filter(|&p| *p == again).count(), 1);
+    }
+
+    #[test]
+    fn folders() {
+        let mut recent = Recent::default();
+        for path in &["a/1.jar", "b/2.jar", "a/3.jar"] {
+            recent.push(Kind::Solver, Path::new(path));
+        }
+
+        assert_eq!(
+            recent.folders(Kind::Solver),
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+    }
+}
@@ -4,16 +4,29 @@ mod workspace;
 use self::generator::GeneratorWidget;
 use self::workspace::WorkspaceWidget;
 
-use gtk::{self, prelude::*, ButtonsType, DialogFlags, FileChooserAction, MessageType};
-use packt_core::problem::Problem;
+use gtk::{
+    self, prelude::*, ButtonsType, DialogFlags, FileChooserAction, MessageType, Orientation,
+    WindowType,
+};
+use packt_core::{
+    problem::Problem,
+    solution::{Evaluation, Solution},
+    viz::{self, VizOptions},
+};
+use recent::{self, Kind, Recent};
 use relm::{Component, ContainerWidget, Relm, Update, Widget};
-use std::{self, fmt, path::PathBuf};
+use std::{
+    self, env, fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 const GLADE_SRC: &str = include_str!("../packt.glade");
 
 #[derive(Msg)]
 pub enum Msg<E: fmt::Display> {
     Import,
+    OpenSolution,
     Save(Problem),
     Err(E),
     Quit,
@@ -43,6 +56,7 @@ impl Update for Win {
         match event {
             Msg::Save(problem) => self.save_problem(&problem),
             Msg::Import => self.import_problem(),
+            Msg::OpenSolution => self.open_solution(),
             Msg::Quit => gtk::main_quit(),
             Msg::Err(e) => {
                 let dialog = self.error_dialog(e);
@@ -83,6 +97,7 @@ impl Widget for Win {
         let workspace = paned.add_widget::<WorkspaceWidget>(());
         connect!(_generator@Moved(ref problem), workspace, Add(problem.clone()));
         connect!(workspace@Import, relm, Msg::Import);
+        connect!(workspace@OpenSolution, relm, Msg::OpenSolution);
         connect!(workspace@Saved(ref problem), relm, Msg::Save(problem.clone()));
         connect!(workspace@Error(ref e), relm, Msg::Err(e.to_string()));
 
@@ -137,7 +152,15 @@ impl Win {
         dialog.add_button("Cancel", cancel);
         dialog.add_button(accept_text, accept);
 
-        if let Ok(p) = std::env::current_dir() {
+        // Start in the folder of the most recent problem, and offer the others as shortcuts
+        let folders = Recent::load().folders(Kind::Problem);
+        for folder in &folders {
+            let _ = dialog.add_shortcut_folder(folder);
+        }
+
+        if let Some(p) = folders.first() {
+            dialog.set_current_folder(p);
+        } else if let Ok(p) = std::env::current_dir() {
             dialog.set_current_folder(p);
         } else if let Some(p) = std::env::home_dir() {
             dialog.set_current_folder(p);
@@ -155,14 +178,16 @@ impl Win {
 
     fn save_problem(&mut self, problem: &Problem) {
         if let Some(path) = self.filechooser_dialog(FileChooserAction::Save) {
-            problem.save(path).unwrap();
+            problem.save(&path).unwrap();
+            recent::remember(Kind::Problem, &path);
         }
     }
 
     fn import_problem(&mut self) {
         if let Some(path) = self.filechooser_dialog(FileChooserAction::Open) {
-            match Problem::from_path(path) {
+            match Problem::from_path(&path) {
                 Ok(problem) => {
+                    recent::remember(Kind::Problem, &path);
                     self.widgets.workspace.emit(workspace::Msg::Add(problem));
                 }
                 Err(_e) => (), /* self.relm.stream().emit(Msg::Err(e.
@@ -170,4 +195,56 @@ impl Win {
             }
         }
     }
+
+    /// Lets the user pick a solution file, then evaluates and draws it.
+    fn open_solution(&mut self) {
+        let path = match self.filechooser_dialog(FileChooserAction::Open) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = Solution::from_path(&path).and_then(|mut solution| {
+            // The solver is not run, so there is no duration to report
+            let evaluation = solution.evaluate(Duration::from_secs(0))?;
+            self.show_solution(&path, &solution, &evaluation)?;
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            let dialog = self.error_dialog(format!("Failed to open {}: {}", path.display(), e));
+            dialog.run();
+            dialog.close();
+        }
+    }
+
+    /// Opens a window with the drawing of `solution` above the text of its `evaluation`.
+    fn show_solution(
+        &self,
+        path: &Path,
+        solution: &Solution,
+        evaluation: &Evaluation,
+    ) -> io::Result<()> {
+        let svg = env::temp_dir().join("packt-solution.svg");
+        let options = VizOptions {
+            legend: true,
+            ..VizOptions::default()
+        };
+        fs::write(&svg, viz::solution_svg(solution, &options))?;
+
+        let window = gtk::Window::new(WindowType::Toplevel);
+        window.set_title(&path.display().to_string());
+        window.set_transient_for(Some(&self.widgets.window));
+
+        let image = gtk::Image::new_from_file(&svg);
+        let label = gtk::Label::new(evaluation.to_string().as_str());
+        label.set_selectable(true);
+
+        let vbox = gtk::Box::new(Orientation::Vertical, 6);
+        vbox.pack_start(&image, true, true, 0);
+        vbox.pack_start(&label, false, false, 0);
+        window.add(&vbox);
+        window.show_all();
+
+        Ok(())
+    }
 }
@@ -2,6 +2,7 @@ use crossbeam_channel::{self, Sender};
 use failure::Error;
 use gtk::{self, prelude::*, Label};
 use packt_core::{problem::Problem, runner, solution::Evaluation};
+use recent::{self, Kind, Recent};
 
 use relm::{Relm, Update, Widget};
 use std::{
@@ -27,22 +28,56 @@ pub struct Entry {
     name: String,
     problem: Problem,
     solutions: Vec<EvalResult>,
+    /// The lowest acceptable filling rate; valid solutions below it count as failures.
+    threshold: f32,
 }
 
 impl Entry {
     fn new(problem: Problem) -> Self {
-        let name = format!(
-            "n={n} h={v} r={r}",
-            v = problem.variant,
-            r = if problem.allow_rotation { "yes" } else { "no" },
-            n = problem.rectangles.len()
-        );
+        let name = problem.name.clone().unwrap_or_else(|| {
+            format!(
+                "n={n} h={v} r={r}",
+                v = problem.variant,
+                r = if problem.allow_rotation { "yes" } else { "no" },
+                n = problem.rectangle_count()
+            )
+        });
 
         Entry {
             id: 0,
             name,
             problem,
             solutions: Vec::new(),
+            threshold: 0.,
+        }
+    }
+}
+
+impl Entry {
+    fn best_filling_rate(&self) -> Option<f32> {
+        self.solutions
+            .iter()
+            .filter_map(|s| s.as_ref().ok())
+            .map(|eval| eval.filling_rate)
+            .fold(None, |best, rate| match best {
+                Some(b) if b >= rate => Some(b),
+                _ => Some(rate),
+            })
+    }
+
+    /// Whether a solution failed, or had a filling rate below the threshold.
+    fn failed(&self) -> bool {
+        self.solutions.iter().any(|s| match s {
+            Ok(eval) => !eval.meets_threshold(self.threshold),
+            Err(_) => true,
+        })
+    }
+
+    /// The text shown for this entry in the problem list.
+    fn label(&self) -> String {
+        match self.best_filling_rate() {
+            Some(rate) => format!("{} ({:.2})", self.name, rate),
+            None => self.name.clone(),
         }
     }
 }
@@ -58,6 +93,10 @@ impl fmt::Display for Entry {
         let mut s = String::new();
         for solution in &self.solutions {
             let eval_string = match solution {
+                Ok(eval) if !eval.meets_threshold(self.threshold) => format!(
+                    "{}\nError: filling rate is below the threshold {:.2}",
+                    eval, self.threshold
+                ),
                 Ok(eval) => eval.to_string(),
                 Err(e) => format!("Error: {}", e),
             };
@@ -66,6 +105,9 @@ impl fmt::Display for Entry {
             s.push_str("\n\n");
         }
 
+        if self.threshold > 0. {
+            s.push_str(&format!("threshold: {:.2}\n", self.threshold));
+        }
         s.push_str(&self.problem.digest());
         write!(f, "{}", s)
     }
@@ -88,11 +130,13 @@ pub struct Model {
     problems: VecDeque<Entry>,
     work_queue: Sender<Job>,
     running: AtomicU32,
+    threshold: f32,
 }
 
 #[derive(Msg)]
 pub enum Msg<E: fmt::Display> {
     Import,
+    OpenSolution,
     Add(Problem),
     Remove,
     Select,
@@ -119,6 +163,7 @@ impl Update for WorkspaceWidget {
             problems: VecDeque::new(),
             work_queue: launch_runner(relm),
             running: AtomicU32::new(0),
+            threshold: 0.,
         }
     }
 
@@ -127,7 +172,7 @@ impl Update for WorkspaceWidget {
 
         let result = match event {
             // taken care of by root widget
-            Import | Saved(_) => Ok(()),
+            Import | OpenSolution | Saved(_) => Ok(()),
             Run => self.run_problems(),
             Completed(id, result) => self.problem_completed(id, result),
             Select => {
@@ -143,7 +188,7 @@ impl Update for WorkspaceWidget {
                     let entry = Entry::new(problem);
                     self.widgets
                         .problems_lb
-                        .insert(&Label::new(entry.name.as_str()), -1);
+                        .insert(&Label::new(entry.label().as_str()), -1);
                     self.widgets.problems_lb.show_all();
                     self.model.problems.push_back(entry.into());
                     self.widgets.run_btn.set_sensitive(true);
@@ -165,7 +210,7 @@ impl Update for WorkspaceWidget {
                 )),
             },
             Error(e) => {
-                eprintln!("Something went wrong: {}", e);
+                error!("Something went wrong: {}", e);
                 Ok(())
             }
         };
@@ -221,6 +266,16 @@ impl Widget for WorkspaceWidget {
             .expect("failed to get import_problem_btn");
         connect!(relm, import_btn, connect_clicked(_), Msg::Import);
 
+        let open_solution_btn: gtk::ToolButton = builder
+            .get_object("open_solution_btn")
+            .expect("failed to get open_solution_btn");
+        connect!(
+            relm,
+            open_solution_btn,
+            connect_clicked(_),
+            Msg::OpenSolution
+        );
+
         let run_btn: gtk::Button = builder
             .get_object("run_button")
             .expect("failed to get run_button");
@@ -230,6 +285,15 @@ impl Widget for WorkspaceWidget {
             .get_object("solver_filechooser")
             .expect("failed to get solver_filechooser");
 
+        // Preselect the last solver that was run, and offer the folders of the others
+        let recent = Recent::load();
+        for folder in recent.folders(Kind::Solver) {
+            let _ = solver_chooser.add_shortcut_folder(folder);
+        }
+        if let Some(solver) = recent.entries(Kind::Solver).first() {
+            solver_chooser.set_filename(solver);
+        }
+
         let retry_spinbtn = builder
             .get_object("retry_spinbtn")
             .expect("failed to get retry_spinbtn");
@@ -284,13 +348,13 @@ impl WorkspaceWidget {
             Some(solver) => solver,
             None => bail!("Please select a solver first"),
         };
+        recent::remember(Kind::Solver, &solver);
 
         let retry = self.widgets.retry_spinbtn.get_value_as_int();
-        let threshold = self.widgets.threshold_spinbtn.get_value();
         let nheights = self.widgets.nwidths_spinbtn.get_value_as_int();
+        self.model.threshold = self.widgets.threshold_spinbtn.get_value() as f32;
 
         env::set_var("RETRY", retry.to_string());
-        env::set_var("THRESHOLD", threshold.to_string());
         env::set_var("N_HEIGHTS", nheights.to_string());
 
         *self.model.running.get_mut() = self.model.problems.len() as u32;
@@ -311,12 +375,49 @@ impl WorkspaceWidget {
 
     fn problem_completed(&mut self, id: usize, result: EvalResult) -> Result<()> {
         let old = self.model.running.fetch_sub(1, Ordering::SeqCst);
+        let threshold = self.model.threshold;
+        match result {
+            Ok(ref eval) if !eval.meets_threshold(threshold) => {
+                warn!(
+                    "Job {}: filling rate {:.2} is below threshold {:.2}",
+                    id, eval.filling_rate, threshold
+                );
+            }
+            Err(ref e) => warn!("Job {} failed: {}", id, e),
+            _ => {}
+        }
+        self.model.problems[id].threshold = threshold;
         self.model.problems[id].solutions.push(result);
+        self.relabel(id)?;
         self.refresh_buffer()?;
 
-        eprintln!("success");
+        info!("Job {} completed", id);
         if old == 1 {
-            eprintln!("All jobs finished");
+            info!("All jobs finished");
+        }
+
+        Ok(())
+    }
+
+    /// Re-renders the list row of entry `id`.
+    ///
+    /// Only the text of the row's label changes; the row itself stays in place, so row
+    /// indices keep mapping onto `self.model.problems` and the selection is unaffected.
+    fn relabel(&mut self, id: usize) -> Result<()> {
+        let label = self
+            .widgets
+            .problems_lb
+            .get_row_at_index(id as i32)
+            .and_then(|row| row.get_child())
+            .and_then(|child| child.downcast::<Label>().ok())
+            .ok_or_else(|| format_err!("failed to get label of row {}", id))?;
+
+        let entry = &self.model.problems[id];
+        let text = escape_markup(&entry.label());
+        if entry.failed() {
+            label.set_markup(&format!("<span foreground=\"red\">{}</span>", text));
+        } else {
+            label.set_markup(&text);
         }
 
         Ok(())
@@ -362,3 +463,9 @@ fn launch_runner(relm: &Relm<WorkspaceWidget>) -> Sender<Job> {
     });
     tx
 }
+
+fn escape_markup(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -3,4 +3,4 @@
 cd ~/dev/dbl-algorithms/packt/packt-core
 cargo build --bin packt-solve --release
 cd ../
-./target/release/packt-solve packt-gtk/solver.jar testcases skyline-minimal.csv
+./target/release/packt-solve --solver packt-gtk/solver.jar testcases skyline-minimal.csv